@@ -0,0 +1,89 @@
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+use super::interpreter::Interpreter;
+use super::value::{expect_string, string_list, NativeFunction, ValueType};
+
+/// the path argument, if the interpreter allows touching files at all
+fn path_arg<'a>(interp: &Interpreter, args: &'a [ValueType], count: usize) -> Result<&'a str, String> {
+    if !interp.capabilities().files {
+        return Err("File access is disabled".to_string());
+    }
+    if args.len() != count {
+        return Err(match count {
+            1 => format!("Function takes exactly 1 argument, got: {}", args.len()),
+            _ => format!("Function takes exactly {count} arguments, got: {}", args.len()),
+        });
+    }
+    expect_string(&args[0])
+}
+
+fn io_error(action: &str, path: &str, err: std::io::Error) -> String {
+    format!("Cannot {action} \"{path}\": {err}")
+}
+
+pub const FILE_FUNCS: [(&str, NativeFunction); 8] = [
+    ("read_file", |interp, args| {
+        let path = path_arg(interp, &args, 1)?;
+        let content = fs::read_to_string(path).map_err(|err| io_error("read", path, err))?;
+        Ok(ValueType::String(content))
+    }),
+    // without the line endings
+    ("read_lines", |interp, args| {
+        let path = path_arg(interp, &args, 1)?;
+        let content = fs::read_to_string(path).map_err(|err| io_error("read", path, err))?;
+        Ok(string_list(content.lines().map(str::to_string), interp.native_loc()))
+    }),
+    // replaces the file if it exists
+    ("write_file", |interp, args| {
+        let path = path_arg(interp, &args, 2)?;
+        fs::write(path, expect_string(&args[1])?).map_err(|err| io_error("write", path, err))?;
+        Ok(ValueType::Unit)
+    }),
+    // creates the file if it does not exist
+    ("append_file", |interp, args| {
+        let path = path_arg(interp, &args, 2)?;
+        let content = expect_string(&args[1])?;
+        fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .and_then(|mut file| file.write_all(content.as_bytes()))
+            .map_err(|err| io_error("write", path, err))?;
+        Ok(ValueType::Unit)
+    }),
+    ("exists", |interp, args| {
+        let path = path_arg(interp, &args, 1)?;
+        Ok(ValueType::Bool(Path::new(path).exists()))
+    }),
+    // names of the entries, sorted
+    ("list_dir", |interp, args| {
+        let path = path_arg(interp, &args, 1)?;
+        let mut names = vec![];
+        for entry in fs::read_dir(path).map_err(|err| io_error("list", path, err))? {
+            let entry = entry.map_err(|err| io_error("list", path, err))?;
+            names.push(entry.file_name().to_string_lossy().into_owned());
+        }
+        names.sort();
+        Ok(string_list(names.into_iter(), interp.native_loc()))
+    }),
+    // including the missing parents
+    ("mkdir", |interp, args| {
+        let path = path_arg(interp, &args, 1)?;
+        fs::create_dir_all(path).map_err(|err| io_error("create", path, err))?;
+        Ok(ValueType::Unit)
+    }),
+    // "remove" is already taken by lists
+    // directories have to be empty
+    ("remove_file", |interp, args| {
+        let path = path_arg(interp, &args, 1)?;
+        let res = if Path::new(path).is_dir() {
+            fs::remove_dir(path)
+        } else {
+            fs::remove_file(path)
+        };
+        res.map_err(|err| io_error("remove", path, err))?;
+        Ok(ValueType::Unit)
+    }),
+];
@@ -0,0 +1,39 @@
+use super::lowexprstmt::{Identifier, LExpr, LStmt, Stmt};
+use super::value::{Closure, ValueType};
+use crate::mref::{MList, MRef};
+
+/// a suspended generator
+/// the interpreter executes its statements one by one, so the state can be saved on any yield
+#[derive(Debug, Clone, PartialEq)]
+pub struct GenState {
+    pub frames: Vec<Frame>,
+    pub scopes: Closure,
+    pub running: bool,
+}
+
+/// a statement which is being executed and may contain a yield
+#[derive(Debug, Clone, PartialEq)]
+pub enum Frame {
+    Block(Vec<LStmt>, usize), // statements and the index of the next one; has its own scope
+    While(LExpr, Vec<LStmt>, Option<Identifier>),
+    For(Identifier, Iter, Vec<LStmt>, Option<Identifier>),
+}
+
+/// state of the iteration over a value
+#[derive(Debug, Clone, PartialEq)]
+pub enum Iter {
+    List(MList, usize),
+    String(Vec<char>, usize),
+    Generator(MRef<GenState>),
+    Instance(ValueType), // has a "next" method
+}
+
+/// statements without a yield can be executed at once
+pub fn has_yield(stmt: &LStmt) -> bool {
+    match &stmt.val {
+        Stmt::Yield(_) => true,
+        Stmt::Block(block) | Stmt::While(_, block, _) | Stmt::For(_, _, block, _) => block.iter().any(has_yield),
+        Stmt::If(blocks) => blocks.iter().any(|(_, block)| block.iter().any(has_yield)),
+        _ => false,
+    }
+}
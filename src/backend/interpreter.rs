@@ -1,28 +1,62 @@
 use std::collections::HashMap;
+use std::io::{self, BufRead, Read};
+use std::mem;
+use std::time::Instant;
 
+use super::generator::{has_yield, Frame, GenState, Iter};
 use super::lowexprstmt::*;
+use super::random::Rng;
 use super::value::*;
 use crate::{
     environment::Environment,
     error::{Error, ErrorType},
     located::{Located, Location},
-    mref::{MList, MMap},
+    mref::{MList, MMap, MRef},
 };
 
 pub fn interpret(builtins: HashMap<String, ValueType>, stmts: Vec<LStmt>) -> Result<(), Error> {
-    Interpreter::new(builtins).interpret(stmts)
+    Interpreter::new(builtins, Capabilities::ALL).interpret(stmts)
+}
+
+/// what the native functions are allowed to do outside of the interpreter
+/// embedders can use it to run untrusted code
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Capabilities {
+    pub files: bool,
+}
+
+impl Capabilities {
+    pub const ALL: Self = Self { files: true };
+    pub const NONE: Self = Self { files: false };
 }
 
 #[derive(Debug)]
 enum InterpErrorType {
     Error(Error),
     Return(Value),
-    Continue,
-    Break,
+    TailCall(Box<(Value, Vec<ValueType>)>), // callee and arguments, made by the function that is returning
+    Throw(Value),
+    Continue(Option<String>),
+    Break(Option<String>, Box<Value>), // label, value for loop expressions
 }
 
 type InterpError = Located<InterpErrorType>;
 
+/// whether break or continue with the label applies to the loop
+fn targets(label: &Option<String>, loop_label: &Option<Identifier>) -> bool {
+    match label {
+        None => true,
+        Some(label) => loop_label.as_ref().is_some_and(|l| &l.val == label),
+    }
+}
+
+/// a call with its callee and arguments already evaluated
+enum PreparedCall {
+    // some calls have to be made right away, e.g. operators overloaded by an instance
+    Done(ValueType),
+    Pending(Value, Vec<ValueType>),
+}
+
 // a miracle
 impl From<Error> for InterpError {
     fn from(value: Error) -> Self {
@@ -35,12 +69,52 @@ impl From<Error> for InterpError {
 
 pub struct Interpreter {
     environment: Environment<ValueType>,
+    // control flow leaving a loop expression, see escape
+    escaping: Option<InterpError>,
+    // test blocks, they are only run on request
+    tests: Vec<(Identifier, Vec<LStmt>)>,
+    // locations of the native functions being called, the innermost last
+    native_calls: Vec<Location>,
+    // an error to report instead of the native function's message, see call_back and exit
+    native_error: Option<Error>,
+    capabilities: Capabilities,
+    // where the program reads input from, stdin if not set
+    input: Option<Box<dyn BufRead>>,
+    // command line arguments of the program
+    args: Vec<String>,
+    rng: Rng,
+    // for measuring time, see the clock function
+    started: Instant,
+}
+
+/// the error of control flow which did not stop where it should have
+fn uncaught(err: InterpError) -> Error {
+    let msg = match err.val {
+        InterpErrorType::Error(error) => return error,
+        InterpErrorType::Return(_) | InterpErrorType::TailCall(_) => ErrorType::ReturnOutsideFunction,
+        InterpErrorType::Throw(val) => ErrorType::UncaughtThrow(val.val),
+        InterpErrorType::Break(..) => ErrorType::BreakOutsideLoop,
+        InterpErrorType::Continue(_) => ErrorType::ContinueOutsideLoop,
+    };
+    Error {
+        msg,
+        lines: vec![err.loc],
+    }
 }
 
 impl Interpreter {
-    pub fn new(defaults: HashMap<String, ValueType>) -> Self {
+    pub fn new(defaults: HashMap<String, ValueType>, capabilities: Capabilities) -> Self {
         Self {
             environment: Environment::new(defaults),
+            escaping: None,
+            tests: vec![],
+            native_calls: vec![],
+            native_error: None,
+            capabilities,
+            input: None,
+            args: vec![],
+            rng: Rng::from_time(),
+            started: Instant::now(),
         }
     }
 
@@ -48,23 +122,27 @@ impl Interpreter {
         // not really needed, but might make a bit less mess when debugging
         self.add_scope();
         for s in stmts {
-            if let Err(err) = self.visit_stmt(s) {
-                let msg = match err.val {
-                    InterpErrorType::Error(error) => return Err(error),
-                    InterpErrorType::Return(_) => ErrorType::ReturnOutsideFunction,
-                    InterpErrorType::Break => ErrorType::BreakOutsideLoop,
-                    InterpErrorType::Continue => ErrorType::ContinueOutsideLoop,
-                };
-                return Err(Error {
-                    msg,
-                    lines: vec![err.loc],
-                });
-            }
+            self.visit_stmt(s).map_err(uncaught)?;
         }
         Ok(())
     }
 
-    #[cfg(test)]
+    /// runs the tests declared so far, each in its own scope
+    /// returns their names and results in the order of declaration
+    /// a test calling exit stops the run, so its Exit is the last result
+    pub fn run_tests(&mut self) -> Vec<(String, Result<(), Error>)> {
+        let mut results = vec![];
+        for (name, block) in mem::take(&mut self.tests) {
+            let res = self.interpret_block(block).map_err(uncaught);
+            let exited = matches!(res, Err(Error { msg: ErrorType::Exit(_), .. }));
+            results.push((name.val, res));
+            if exited {
+                break;
+            }
+        }
+        results
+    }
+
     pub fn get_val(&self, name: String) -> Option<ValueType> {
         self.environment.get(&name)
     }
@@ -93,23 +171,57 @@ impl Interpreter {
 impl Interpreter {
     fn visit_stmt(&mut self, stmt: LStmt) -> Result<(), InterpError> {
         let loc = stmt.loc;
-        match stmt.val {
+        let res = match stmt.val {
             Stmt::Expr(expr) => self.expr(loc, expr),
             Stmt::VarDecl(ident, expr) => self.var_decl(loc, ident, expr),
             Stmt::Assign(ident, expr) => self.assignment(loc, ident, expr),
             Stmt::AssignIndex(ls, idx, val) => self.assignindex(loc, ls, idx, val),
             Stmt::Block(block) => self.block(loc, block),
             Stmt::If(blocks) => self.if_else(loc, blocks),
-            Stmt::While(cond, block) => self.whiles(loc, cond, block),
+            Stmt::While(cond, block, label) => self.whiles(loc, cond, block, label),
             Stmt::Return(expr) => self.retur(loc, expr),
-            Stmt::Break => self.brek(loc),
-            Stmt::Continue => self.cont(loc),
+            Stmt::TailCall(expr) => self.tail_call(loc, expr),
+            Stmt::Break(label, expr) => self.brek(loc, label, expr),
+            Stmt::Continue(label) => self.cont(loc, label),
             Stmt::Struct(name, fields) => self.struc(loc, name, fields),
             Stmt::AssignStruct(expr1, name, expr2) => self.assignstruc(loc, expr1, name, expr2),
-            Stmt::Impl(name, block) => self.imp(loc, name, block),
+            Stmt::Impl(name, trait_name, block) => self.imp(loc, name, trait_name, block),
+            Stmt::Throw(expr) => self.throw(loc, expr),
+            Stmt::Trait(name, required, defaults) => self.trai(loc, name, required, defaults),
+            Stmt::Yield(_) => unreachable!("Yield outside generator should be checked in varcheck"),
+            Stmt::For(ident, expr, block, label) => self.fors(loc, ident, expr, block, label),
+            Stmt::Assert(cond, msg) => self.asser(loc, cond, msg),
+            Stmt::Test(name, block) => {
+                self.tests.push((name, block));
+                Ok(())
+            }
+        };
+        self.escaped(res)
+    }
+
+    /// control flow which left an expression replaces the error it left it as, see escape
+    fn escaped<T>(&mut self, res: Result<T, InterpError>) -> Result<T, InterpError> {
+        match self.escaping.take() {
+            Some(err) if res.is_err() => Err(err),
+            _ => res,
         }
     }
 
+    /// control flow can leave an expression only as an error
+    /// the actual one waits until it reaches the statement containing the expression
+    fn escape(&mut self, err: InterpError) -> Error {
+        let msg = match err.val {
+            InterpErrorType::Error(error) => return error,
+            InterpErrorType::Return(_) | InterpErrorType::TailCall(_) => ErrorType::ReturnOutsideFunction,
+            InterpErrorType::Throw(ref val) => ErrorType::UncaughtThrow(val.val.clone()),
+            InterpErrorType::Break(..) => ErrorType::BreakOutsideLoop,
+            InterpErrorType::Continue(_) => ErrorType::ContinueOutsideLoop,
+        };
+        let lines = vec![err.loc];
+        self.escaping = Some(err);
+        Error { msg, lines }
+    }
+
     fn var_decl(&mut self, _: Location, ident: Identifier, expr: LExpr) -> Result<(), InterpError> {
         let name = ident.val;
         let val = self.visit_expr(expr)?;
@@ -158,17 +270,21 @@ impl Interpreter {
         Ok(())
     }
 
+    fn condition(&mut self, cond: LExpr) -> Result<bool, Error> {
+        let cond = self.visit_expr(cond)?;
+        let ValueType::Bool(b) = cond.val else {
+            return Err(Error {
+                msg: ErrorType::ExpectedBool,
+                lines: vec![cond.loc],
+            });
+        };
+        Ok(b)
+    }
+
     fn if_else(&mut self, _: Location, blocks: Vec<(LExpr, Vec<LStmt>)>) -> Result<(), InterpError> {
         for (cond, block) in blocks {
-            let ValueType::Bool(cond2) = self.visit_expr(cond.clone())?.val else {
-                return Err(Error {
-                    msg: ErrorType::ExpectedBool,
-                    lines: vec![cond.loc],
-                }
-                .into());
-            };
             // do not continue
-            if cond2 {
+            if self.condition(cond)? {
                 self.interpret_block(block)?;
                 break;
             }
@@ -177,25 +293,42 @@ impl Interpreter {
         Ok(())
     }
 
-    fn whiles(&mut self, _: Location, cond: LExpr, block: Vec<LStmt>) -> Result<(), InterpError> {
+    fn whiles(&mut self, _: Location, cond: LExpr, block: Vec<LStmt>, label: Option<Identifier>) -> Result<(), InterpError> {
         loop {
-            let cond = self.visit_expr(cond.clone())?;
-            let ValueType::Bool(b) = cond.val else {
-                return Err(Error {
-                    msg: ErrorType::ExpectedBool,
-                    lines: vec![cond.loc],
-                }
-                .into());
-            };
-            if !b {
+            if !self.condition(cond.clone())? {
                 break;
             }
             if let Err(err) = self.interpret_block(block.clone()) {
                 match err.val {
-                    InterpErrorType::Error(_) => return Err(err),
-                    InterpErrorType::Return(_) => return Err(err),
-                    InterpErrorType::Continue => continue,
-                    InterpErrorType::Break => break,
+                    InterpErrorType::Continue(l) if targets(&l, &label) => continue,
+                    InterpErrorType::Break(l, _) if targets(&l, &label) => break,
+                    _ => return Err(err),
+                };
+            }
+        }
+        Ok(())
+    }
+
+    fn fors(
+        &mut self,
+        _: Location,
+        ident: Identifier,
+        expr: LExpr,
+        block: Vec<LStmt>,
+        label: Option<Identifier>,
+    ) -> Result<(), InterpError> {
+        let loc = expr.loc;
+        let val = self.visit_expr(expr)?;
+        let mut iter = self.get_iter(val)?;
+        while let Some(val) = self.iter_next(&mut iter, loc)? {
+            self.environment.add_scope_vars(HashMap::from([(ident.val.clone(), val)]));
+            let res = self.interpret_block(block.clone());
+            self.remove_scope();
+            if let Err(err) = res {
+                match err.val {
+                    InterpErrorType::Continue(l) if targets(&l, &label) => continue,
+                    InterpErrorType::Break(l, _) if targets(&l, &label) => break,
+                    _ => return Err(err),
                 };
             }
         }
@@ -207,15 +340,22 @@ impl Interpreter {
         let _ = self.visit_expr(expr)?;
         Ok(())
     }
-    fn brek(&mut self, loc: Location) -> Result<(), InterpError> {
+    fn brek(&mut self, loc: Location, label: Option<Identifier>, expr: Option<LExpr>) -> Result<(), InterpError> {
+        let val = match expr {
+            Some(expr) => self.visit_expr(expr)?,
+            None => Value {
+                val: ValueType::Unit,
+                loc,
+            },
+        };
         Err(InterpError {
-            val: InterpErrorType::Break,
+            val: InterpErrorType::Break(label.map(|l| l.val), val.into()),
             loc,
         })
     }
-    fn cont(&mut self, loc: Location) -> Result<(), InterpError> {
+    fn cont(&mut self, loc: Location, label: Option<Identifier>) -> Result<(), InterpError> {
         Err(InterpError {
-            val: InterpErrorType::Continue,
+            val: InterpErrorType::Continue(label.map(|l| l.val)),
             loc,
         })
     }
@@ -226,7 +366,74 @@ impl Interpreter {
             loc,
         })
     }
-    fn struc(&mut self, _: Location, name: Identifier, fields: Vec<Identifier>) -> Result<(), InterpError> {
+    fn tail_call(&mut self, loc: Location, expr: LExpr) -> Result<(), InterpError> {
+        let call_loc = expr.loc;
+        let call = match expr.val {
+            Expr::Call(callee, args) => self.prepare_call(*callee, args, call_loc)?,
+            Expr::MethodAccess(callee, name, args) => {
+                let callee2 = self.visit_expr(*callee)?;
+                self.prepare_method(call_loc, callee2, name, args)?
+            }
+            _ => unreachable!("Only calls can be tail calls"),
+        };
+        let val = match call {
+            PreparedCall::Done(val) => val,
+            // only functions need the stack, the rest can be called right away
+            PreparedCall::Pending(callee, args) if matches!(callee.val, ValueType::Function(..)) => {
+                return Err(InterpError {
+                    val: InterpErrorType::TailCall(Box::new((callee, args))),
+                    loc: call_loc,
+                });
+            }
+            PreparedCall::Pending(callee, args) => self.call_value(callee, args, call_loc)?,
+        };
+        Err(InterpError {
+            val: InterpErrorType::Return(Value { val, loc: call_loc }),
+            loc,
+        })
+    }
+    fn throw(&mut self, loc: Location, expr: LExpr) -> Result<(), InterpError> {
+        let val = self.visit_expr(expr)?;
+        Err(InterpError {
+            val: InterpErrorType::Throw(val),
+            loc,
+        })
+    }
+    fn asser(&mut self, _: Location, cond: LExpr, msg: Option<LExpr>) -> Result<(), InterpError> {
+        // points at the condition, so that the message shows what failed
+        let loc = cond.loc;
+        if self.condition(cond)? {
+            return Ok(());
+        }
+        let msg = match msg.map(|m| self.visit_expr(m)).transpose()? {
+            Some(Value {
+                val: ValueType::String(s),
+                ..
+            }) => Some(s),
+            Some(val) => Some(val.val.to_string()),
+            None => None,
+        };
+        Err(Error {
+            msg: ErrorType::AssertionFailed(msg),
+            lines: vec![loc],
+        }
+        .into())
+    }
+    fn struc(&mut self, _: Location, name: Identifier, fields: Vec<(Identifier, Option<LExpr>)>) -> Result<(), InterpError> {
+        // defaults are evaluated anew for every instance, so they are kept as functions without parameters
+        let fields = fields
+            .into_iter()
+            .map(|(f, default)| {
+                let default = default.map(|d| {
+                    let body = vec![LStmt {
+                        loc: d.loc,
+                        val: Stmt::Return(d),
+                    }];
+                    ValueType::Function(vec![], body, self.environment.scopes.clone())
+                });
+                (f, default)
+            })
+            .collect();
         if !self.environment.insert(
             &name.val,
             ValueType::Struct(name.clone(), fields, HashMap::new().into()),
@@ -237,7 +444,7 @@ impl Interpreter {
     }
     fn assignstruc(&mut self, _: Location, expr1: LExpr, name: Identifier, expr2: LExpr) -> Result<(), InterpError> {
         let expr = self.visit_expr(expr1)?;
-        let ValueType::Instance(_, mut fields) = expr.val else {
+        let ValueType::Instance(_, mut fields, _) = expr.val else {
             return Err(Error {
                 msg: ErrorType::ExpectedInstance,
                 lines: vec![expr.loc],
@@ -256,7 +463,13 @@ impl Interpreter {
         fields.insert(name.val, val.val);
         Ok(())
     }
-    fn imp(&mut self, _: Location, name: Identifier, block: Vec<LStmt>) -> Result<(), InterpError> {
+    fn imp(
+        &mut self,
+        _: Location,
+        name: Identifier,
+        trait_name: Option<Identifier>,
+        block: Vec<LStmt>,
+    ) -> Result<(), InterpError> {
         // its existence is checked in varcheck
         // and though it may be reassigned, the name still MUST exist
         // it does not have to be a struct anymore though
@@ -268,12 +481,56 @@ impl Interpreter {
             }
             .into());
         };
+        let defaults = match trait_name {
+            Some(trait_name) => {
+                // same as the struct, it is checked in varcheck but might have been reassigned since
+                let trai = self.environment.get(&trait_name.val).expect("Trait somehow not defined");
+                let ValueType::Trait(_, _, defaults) = trai else {
+                    return Err(Error {
+                        msg: ErrorType::NotATrait(trait_name.val),
+                        lines: vec![trait_name.loc],
+                    }
+                    .into());
+                };
+                Some(defaults)
+            }
+            None => None,
+        };
+        let mut implemented = vec![];
         for s in block {
             let Stmt::VarDecl(name, fun) = s.val else {
                 unreachable!("Checked for in varcheck");
             };
+            implemented.push(name.val.clone());
             methods.insert(name.val, self.visit_expr(fun)?.val);
         }
+        // only the ones that were not overriden
+        if let Some(defaults) = defaults {
+            for (name, fun) in defaults.iter() {
+                if !implemented.contains(&name) {
+                    methods.insert(name, fun);
+                }
+            }
+        }
+        Ok(())
+    }
+    fn trai(
+        &mut self,
+        _: Location,
+        name: Identifier,
+        required: Vec<Identifier>,
+        defaults: Vec<LStmt>,
+    ) -> Result<(), InterpError> {
+        let mut methods = MMap::new(HashMap::new());
+        for s in defaults {
+            let Stmt::VarDecl(name, fun) = s.val else {
+                unreachable!("Checked for in the parser");
+            };
+            methods.insert(name.val, self.visit_expr(fun)?.val);
+        }
+        if !self.environment.insert(&name.val, ValueType::Trait(name.clone(), required, methods)) {
+            unreachable!("Item \"{}\" already declared\nLocation: {:?}", name.val, name.loc);
+        }
         Ok(())
     }
 }
@@ -284,6 +541,7 @@ impl Interpreter {
         let val = match expr.val {
             Expr::Unit => self.unit(),
             Expr::Int(n) => self.int(n),
+            Expr::BigInt(n) => Ok(ValueType::BigInt(n)),
             Expr::Float(n) => self.float(n),
             Expr::String(s) => self.string(s),
             Expr::Bool(b) => self.bool(b),
@@ -292,18 +550,36 @@ impl Interpreter {
             Expr::List(ls) => self.list(loc, ls),
             Expr::Index(expr2, idx) => self.index(loc, *expr2, *idx),
             Expr::Lambda(params, body) => self.lambda(loc, params, body),
+            Expr::GenLambda(params, body) => self.gen_lambda(loc, params, body),
             Expr::FieldAccess(expr, name) => self.field(loc, *expr, name),
             Expr::MethodAccess(expr, name, args) => self.method(loc, *expr, name, args),
+            Expr::SafeFieldAccess(expr, name) => self.safe_field(loc, *expr, name),
+            Expr::SafeMethodAccess(expr, name, args) => self.safe_method(loc, *expr, name, args),
+            Expr::Coalesce(left, right) => self.coalesce(*left, *right),
+            Expr::StructLiteral(name, fields, base) => self.struct_literal(loc, name, fields, base.map(|b| *b)),
+            Expr::Loop(block, label) => self.loops(block, label),
         }?;
         Ok(Value { val, loc: expr.loc })
     }
+    fn loops(&mut self, block: Vec<LStmt>, label: Option<Identifier>) -> Result<ValueType, Error> {
+        loop {
+            let Err(err) = self.interpret_block(block.clone()) else {
+                continue;
+            };
+            match err.val {
+                InterpErrorType::Continue(l) if targets(&l, &label) => continue,
+                InterpErrorType::Break(l, val) if targets(&l, &label) => return Ok(val.val),
+                _ => return Err(self.escape(err)),
+            }
+        }
+    }
     fn unit(&mut self) -> Result<ValueType, Error> {
         Ok(ValueType::Unit)
     }
-    fn int(&mut self, n: i32) -> Result<ValueType, Error> {
+    fn int(&mut self, n: i64) -> Result<ValueType, Error> {
         Ok(ValueType::Int(n))
     }
-    fn float(&mut self, n: f32) -> Result<ValueType, Error> {
+    fn float(&mut self, n: f64) -> Result<ValueType, Error> {
         Ok(ValueType::Float(n))
     }
     fn identifier(&mut self, ident: String, loc: Location) -> Result<ValueType, Error> {
@@ -318,22 +594,90 @@ impl Interpreter {
         Ok(ValueType::Bool(b))
     }
     fn call(&mut self, callee: LExpr, args: Vec<LExpr>, loc: Location) -> Result<ValueType, Error> {
+        match self.prepare_call(callee, args, loc)? {
+            PreparedCall::Done(val) => Ok(val),
+            PreparedCall::Pending(callee, args) => self.call_value(callee, args, loc),
+        }
+    }
+    fn prepare_call(&mut self, callee: LExpr, args: Vec<LExpr>, loc: Location) -> Result<PreparedCall, Error> {
         let mut args2 = vec![];
         for arg in args {
             args2.push(self.visit_expr(arg)?.val);
         }
 
+        // native operators may be overloaded by the instance's method
+        let op = match &callee.val {
+            Expr::Identifier(name) if NATIVE_OPERATORS.iter().any(|(op, _, _)| op == name) => Some(name.clone()),
+            _ => None,
+        };
+        let is_unary = matches!(&callee.val, Expr::Identifier(name) if name == "$$neg" || name == "$$not");
         let callee = self.visit_expr(callee)?;
+        if let ValueType::NativeFunction(func) = callee.val {
+            if let Some(op) = &op {
+                if let Some(val) = self.call_operator_method(op, &args2, loc)? {
+                    return Ok(PreparedCall::Done(val));
+                }
+            }
+            // e.g. overflow is reported at the operator itself
+            if op.is_some() || is_unary {
+                return Ok(PreparedCall::Done(self.call_fn_native(func, args2, callee.loc)?));
+            }
+        }
+        Ok(PreparedCall::Pending(callee, args2))
+    }
+    fn call_value(&mut self, callee: Value, args: Vec<ValueType>, loc: Location) -> Result<ValueType, Error> {
         match callee.val {
-            ValueType::NativeFunction(func) => self.call_fn_native(func, args2, loc),
-            ValueType::Function(params, body, closure) => self.call_fn(params, body, closure, args2, loc),
-            ValueType::Struct(name, fields, methods) => self.call_struct(name, fields, args2, methods, loc),
+            ValueType::NativeFunction(func) => self.call_fn_native(func, args, loc),
+            ValueType::Function(params, body, closure) => self.call_fn(params, body, closure, args, loc),
+            ValueType::GenFunction(params, body, closure) => self.call_gen_fn(params, body, closure, args, loc),
+            ValueType::Struct(name, fields, methods) => self.call_struct(name, fields, args, methods, loc),
             _ => Err(Error {
                 msg: ErrorType::ItemNotCalleable,
                 lines: vec![callee.loc],
             }),
         }
     }
+    /// calls the method overloading the operator if the left operand is an instance
+    /// the right operand is never dispatched on, as its method would get the operands in the wrong order
+    /// comparisons not defined by the struct are derived from "==" and "<"
+    /// returns None when there is nothing to dispatch to and the native operator should be used
+    fn call_operator_method(&mut self, op: &str, args: &[ValueType], loc: Location) -> Result<Option<ValueType>, Error> {
+        let [ValueType::Instance(_, _, methods), right] = args else {
+            return Ok(None);
+        };
+        if let Some(met) = methods.get(&op.to_string()) {
+            let met = Value { val: met.clone(), loc };
+            return self.call_value(met, args.to_vec(), loc).map(Some);
+        }
+        let compare = |this: &mut Self, op: &str| -> Result<Option<bool>, Error> {
+            let Some(met) = methods.get(&op.to_string()) else {
+                return Ok(None);
+            };
+            let met = Value { val: met.clone(), loc };
+            match this.call_value(met, vec![args[0].clone(), right.clone()], loc)? {
+                ValueType::Bool(b) => Ok(Some(b)),
+                _ => Err(Error {
+                    msg: ErrorType::InvalidOperatorResult(op.to_string()),
+                    lines: vec![loc],
+                }),
+            }
+        };
+        let res = match op {
+            "!=" => compare(self, "==")?.map(|eq| !eq),
+            ">=" => compare(self, "<")?.map(|lt| !lt),
+            "<=" | ">" => {
+                let Some(lt) = compare(self, "<")? else {
+                    return Ok(None);
+                };
+                let Some(eq) = compare(self, "==")? else {
+                    return Ok(None);
+                };
+                Some(if op == "<=" { lt || eq } else { !(lt || eq) })
+            }
+            _ => None,
+        };
+        Ok(res.map(ValueType::Bool))
+    }
     fn list(&mut self, _: Location, ls: Vec<LExpr>) -> Result<ValueType, Error> {
         // a nicer version, but requires cloning...
         /*
@@ -365,8 +709,10 @@ impl Interpreter {
                 Ok(ls.read(|l| l[n2].clone()).val)
             }
             ValueType::String(s) => {
-                let n2 = MList::check_index(n, s.len()).ok_or_else(|| Error {
-                    msg: ErrorType::IndexOutOfRange(n, s.len()),
+                // indexed by characters, not bytes
+                let len = s.chars().count();
+                let n2 = MList::check_index(n, len).ok_or_else(|| Error {
+                    msg: ErrorType::IndexOutOfRange(n, len),
                     lines: vec![loc],
                 })?;
                 Ok(ValueType::String(s.chars().nth(n2).unwrap().to_string()))
@@ -384,13 +730,28 @@ impl Interpreter {
         }
         Ok(ValueType::Function(params2, body, self.environment.scopes.clone()))
     }
+    fn gen_lambda(&mut self, _: Location, params: Vec<Identifier>, body: Vec<LStmt>) -> Result<ValueType, Error> {
+        let params2 = params.into_iter().map(|p| p.val).collect();
+        Ok(ValueType::GenFunction(params2, body, self.environment.scopes.clone()))
+    }
     fn field(&mut self, loc: Location, expr: LExpr, name: Identifier) -> Result<ValueType, Error> {
         let expr2 = self.visit_expr(expr)?;
-        let ValueType::Instance(struct_name, fields) = expr2.val else {
-            return Err(Error {
-                msg: ErrorType::ExpectedInstance,
-                lines: vec![expr2.loc],
-            });
+        self.get_field(loc, expr2, name)
+    }
+    fn get_field(&mut self, loc: Location, expr2: Value, name: Identifier) -> Result<ValueType, Error> {
+        // structs hold their associated functions and constants, instances look in their fields first
+        let (struct_name, fields) = match expr2.val {
+            ValueType::Instance(struct_name, fields, methods) => match fields.get(&name.val) {
+                Some(val) => return Ok(val.clone()),
+                None => (struct_name, methods),
+            },
+            ValueType::Struct(struct_name, _, methods) => (struct_name.val, methods),
+            _ => {
+                return Err(Error {
+                    msg: ErrorType::ExpectedInstance,
+                    lines: vec![expr2.loc],
+                })
+            }
         };
         Ok(fields
             .get(&name.val)
@@ -402,130 +763,626 @@ impl Interpreter {
     }
     fn method(&mut self, loc: Location, callee: LExpr, name: Identifier, args: Vec<LExpr>) -> Result<ValueType, Error> {
         let callee2 = self.visit_expr(callee)?;
-
-        let mut args2 = vec![callee2.val.clone()];
+        self.call_method(loc, callee2, name, args)
+    }
+    fn call_method(&mut self, loc: Location, callee2: Value, name: Identifier, args: Vec<LExpr>) -> Result<ValueType, Error> {
+        match self.prepare_method(loc, callee2, name, args)? {
+            PreparedCall::Done(val) => Ok(val),
+            PreparedCall::Pending(met, args) => self.call_value(met, args, loc),
+        }
+    }
+    fn prepare_method(&mut self, loc: Location, callee2: Value, name: Identifier, args: Vec<LExpr>) -> Result<PreparedCall, Error> {
+        if let ValueType::Generator(gen) = callee2.val {
+            return self.generator_method(loc, gen, name, args).map(PreparedCall::Done);
+        }
+        // only instances are passed to the method, associated functions do not get a receiver
+        let mut args2 = match callee2.val {
+            ValueType::Instance(..) => vec![callee2.val.clone()],
+            _ => vec![],
+        };
         for arg in args {
             args2.push(self.visit_expr(arg)?.val);
         }
 
-        let ValueType::Instance(struct_name, fields) = callee2.val else {
-            return Err(Error {
-                msg: ErrorType::ExpectedInstance,
-                lines: vec![callee2.loc],
-            });
+        let callee_loc = callee2.loc;
+        let struct_name = match &callee2.val {
+            ValueType::Struct(struct_name, ..) => Some(struct_name.val.clone()),
+            _ => None,
         };
-        let met = fields
-            .get(&name.val)
-            .ok_or_else(|| Error {
-                msg: ErrorType::FieldNotFound(name.val, struct_name),
-                lines: vec![loc],
-            })?
-            .clone();
+        let (method_name, name_loc) = (name.val.clone(), name.loc);
+        let met = self.get_field(loc, callee2, name)?;
+        // varcheck only catches this when the struct is called by its name
+        if let (Some(struct_name), ValueType::Function(params, ..) | ValueType::GenFunction(params, ..)) = (struct_name, &met) {
+            if params.first().is_some_and(|p| p == "self") {
+                return Err(Error {
+                    msg: ErrorType::MethodNeedsInstance(method_name, struct_name),
+                    lines: vec![callee_loc, name_loc],
+                });
+            }
+        }
+        Ok(PreparedCall::Pending(Value { val: met, loc: callee_loc }, args2))
+    }
 
-        match met {
-            ValueType::NativeFunction(func) => self.call_fn_native(func, args2, loc),
-            ValueType::Function(params, body, closure) => self.call_fn(params, body, closure, args2, loc),
-            ValueType::Struct(name, fields, methods) => self.call_struct(name, fields, args2, methods, loc),
-            _ => Err(Error {
-                msg: ErrorType::ItemNotCalleable,
-                lines: vec![callee2.loc],
-            }),
+    fn safe_field(&mut self, loc: Location, expr: LExpr, name: Identifier) -> Result<ValueType, Error> {
+        let expr2 = self.visit_expr(expr)?;
+        match expr2.val {
+            ValueType::Option(None) => Ok(ValueType::Option(None)),
+            ValueType::Option(Some(val)) => self.get_field(loc, Value { val: *val, loc: expr2.loc }, name),
+            _ => self.get_field(loc, expr2, name),
+        }
+    }
+    fn safe_method(&mut self, loc: Location, callee: LExpr, name: Identifier, args: Vec<LExpr>) -> Result<ValueType, Error> {
+        let callee2 = self.visit_expr(callee)?;
+        // the arguments are not evaluated at all when short-circuiting
+        match callee2.val {
+            ValueType::Option(None) => Ok(ValueType::Option(None)),
+            ValueType::Option(Some(val)) => self.call_method(loc, Value { val: *val, loc: callee2.loc }, name, args),
+            _ => self.call_method(loc, callee2, name, args),
+        }
+    }
+    fn coalesce(&mut self, left: LExpr, right: LExpr) -> Result<ValueType, Error> {
+        // anything that is not an option is considered present
+        match self.visit_expr(left)?.val {
+            ValueType::Option(None) => Ok(self.visit_expr(right)?.val),
+            ValueType::Option(Some(val)) => Ok(*val),
+            val => Ok(val),
         }
     }
 
     fn call_fn(
         &mut self,
-        params: Vec<String>,
-        body: Vec<LStmt>,
-        closure: Closure,
-        args: Vec<ValueType>,
-        loc: Location,
+        mut params: Vec<String>,
+        mut body: Vec<LStmt>,
+        mut closure: Closure,
+        mut args: Vec<ValueType>,
+        mut loc: Location,
     ) -> Result<ValueType, Error> {
-        if args.len() != params.len() {
+        // tail calls replace the current call instead of nesting, so the stack does not grow
+        loop {
+            if args.len() != params.len() {
+                return Err(Error {
+                    msg: ErrorType::IncorrectParameterCount(args.len(), params.len()),
+                    lines: vec![loc],
+                });
+            }
+            // craftinginterpreters seem to do it
+            let env = mem::replace(&mut self.environment, Environment { scopes: closure });
+            self.environment.add_scope_vars(
+                params
+                    .iter()
+                    .zip(args)
+                    .map(|(n, v)| (n.clone(), v))
+                    .collect::<HashMap<_, _>>(),
+            );
+            let res = self.interpret_block(body);
+            self.environment = env;
+
+            let Err(err) = res else {
+                return Ok(ValueType::Unit); // hope this doesnt bite me later...
+            };
+            let msg = match err.val {
+                InterpErrorType::Error(err) => return Err(err),
+                InterpErrorType::Return(val) => return Ok(val.val),
+                InterpErrorType::TailCall(call) => {
+                    let (callee, args2) = *call;
+                    let ValueType::Function(params2, body2, closure2) = callee.val else {
+                        unreachable!("Only functions are called as tail calls");
+                    };
+                    (params, body, closure, args, loc) = (params2, body2, closure2, args2, err.loc);
+                    continue;
+                }
+                // thrown values just keep going up until they reach the top
+                InterpErrorType::Throw(val) => ErrorType::UncaughtThrow(val.val),
+                InterpErrorType::Break(..) => ErrorType::BreakOutsideLoop,
+                InterpErrorType::Continue(_) => ErrorType::ContinueOutsideLoop,
+            };
             return Err(Error {
-                msg: ErrorType::IncorrectParameterCount(args.len(), params.len()),
-                lines: vec![loc],
+                msg,
+                lines: vec![err.loc],
             });
         }
-        // craftinginterpreters seem to do it
-        let env = self.environment.clone();
-        self.environment = Environment { scopes: closure };
-        self.environment.add_scope_vars(
-            params
-                .iter()
-                .zip(args)
-                .map(|(n, v)| (n.clone(), v))
-                .collect::<HashMap<_, _>>(),
-        );
-
-        let val = match self.interpret_block(body) {
-            Ok(..) => ValueType::Unit, // hope this doesnt bite me later...
-            Err(err) => match err.val {
-                InterpErrorType::Error(err) => return Err(err),
-                InterpErrorType::Return(val) => val.val,
-                InterpErrorType::Break => {
-                    return Err(Error {
-                        msg: ErrorType::BreakOutsideLoop,
-                        lines: vec![err.loc],
-                    });
-                }
-                InterpErrorType::Continue => {
-                    return Err(Error {
-                        msg: ErrorType::ContinueOutsideLoop,
-                        lines: vec![err.loc],
-                    });
-                }
-            },
+    }
+
+    fn call_fn_native(&mut self, func: NativeFunction, args: Vec<ValueType>, loc: Location) -> Result<ValueType, Error> {
+        self.native_calls.push(loc);
+        let res = func(self, args);
+        self.native_calls.pop();
+        // taken even on success, the native might have recovered from it
+        let callback_error = self.native_error.take();
+        res.map_err(|msg| {
+            callback_error.unwrap_or(Error {
+                msg: ErrorType::NativeFunctionError(msg),
+                lines: vec![loc],
+            })
+        })
+    }
+
+    pub fn capabilities(&self) -> Capabilities {
+        self.capabilities
+    }
+
+    pub fn set_args(&mut self, args: Vec<String>) {
+        self.args = args;
+    }
+
+    pub fn args(&self) -> &[String] {
+        &self.args
+    }
+
+    /// the random number generator, kept here so that a seed makes the whole run reproducible
+    pub fn rng(&mut self) -> &mut Rng {
+        &mut self.rng
+    }
+
+    /// when the interpreter was created
+    pub fn started(&self) -> Instant {
+        self.started
+    }
+
+    /// stops the program by unwinding all the way out of interpret, with Exit as the error
+    /// the native function calling it should return the message
+    pub fn exit(&mut self, code: i32) -> String {
+        let err = Error {
+            msg: ErrorType::Exit(code),
+            lines: vec![self.native_loc()],
         };
-        self.remove_scope();
-        self.environment = env;
-        Ok(val)
+        let msg = err.msg.msg();
+        self.native_error = Some(err);
+        msg
     }
 
-    // btw the self is technically not needed
-    // leaving it here for style for now
-    fn call_fn_native(&self, func: NativeFunction, args: Vec<ValueType>, loc: Location) -> Result<ValueType, Error> {
-        func(args).map_err(|msg| Error {
-            msg: ErrorType::NativeFunctionError(msg),
-            lines: vec![loc],
+    /// replaces stdin as the input of the program
+    pub fn set_input(&mut self, input: Box<dyn BufRead>) {
+        self.input = Some(input);
+    }
+
+    /// the next line of input without the line ending, None at the end
+    pub fn read_line(&mut self) -> io::Result<Option<String>> {
+        let mut line = String::new();
+        let read = match &mut self.input {
+            Some(input) => input.read_line(&mut line)?,
+            None => io::stdin().read_line(&mut line)?,
+        };
+        if read == 0 {
+            return Ok(None);
+        }
+        let line = line.strip_suffix('\n').unwrap_or(&line);
+        Ok(Some(line.strip_suffix('\r').unwrap_or(line).to_string()))
+    }
+
+    /// the rest of the input
+    pub fn read_all(&mut self) -> io::Result<String> {
+        let mut s = String::new();
+        match &mut self.input {
+            Some(input) => input.read_to_string(&mut s)?,
+            None => io::stdin().read_to_string(&mut s)?,
+        };
+        Ok(s)
+    }
+
+    /// location of the call to the native function currently running
+    pub fn native_loc(&self) -> Location {
+        *self
+            .native_calls
+            .last()
+            .expect("Only native functions ask for their location")
+    }
+
+    /// calls a value from inside a native function
+    /// if the call fails and the native returns an error, the original error is reported
+    /// instead of the message, so that it keeps its location
+    pub fn call_back(&mut self, callee: ValueType, args: Vec<ValueType>) -> Result<ValueType, String> {
+        let loc = self.native_loc();
+        let res = self.call_value(Value { val: callee, loc }, args, loc);
+        self.keep_error(res)
+    }
+
+    /// starts iterating over the value the same way as a for loop does
+    /// errors are kept the same way as with call_back
+    pub fn iter(&mut self, val: ValueType) -> Result<Iter, String> {
+        let loc = self.native_loc();
+        let res = self.get_iter(Value { val, loc });
+        self.keep_error(res)
+    }
+
+    /// the next value of the iteration, None at the end
+    pub fn next_item(&mut self, iter: &mut Iter) -> Result<Option<ValueType>, String> {
+        let loc = self.native_loc();
+        let res = self.iter_next(iter, loc);
+        self.keep_error(res)
+    }
+
+    /// applies the builtin operator the same way as the code would, so that instances can overload it
+    pub fn operator(&mut self, op: &str, left: ValueType, right: ValueType) -> Result<ValueType, String> {
+        let loc = self.native_loc();
+        let args = vec![left, right];
+        let res = self.call_operator_method(op, &args, loc);
+        if let Some(val) = self.keep_error(res)? {
+            return Ok(val);
+        }
+        let (_, _, func) = NATIVE_OPERATORS
+            .iter()
+            .find(|(name, _, _)| *name == op)
+            .expect("Only builtin operators can be applied");
+        func(self, args)
+    }
+
+    /// the error is reported instead of the message the native function returns
+    fn keep_error<T>(&mut self, res: Result<T, Error>) -> Result<T, String> {
+        res.map_err(|err| {
+            let msg = err.msg.msg();
+            self.native_error = Some(err);
+            msg
         })
     }
 
     // btw the self is technically not needed 2
     // leaving it here for style for now
     fn call_struct(
-        &self,
+        &mut self,
         name: Identifier,
-        fields: Vec<Identifier>,
+        fields: Vec<(Identifier, Option<ValueType>)>,
         args: Vec<ValueType>,
         methods: MMap<ValueType>,
         loc: Location,
     ) -> Result<ValueType, Error> {
-        if args.len() != fields.len() {
+        // only the trailing fields with defaults can be left out
+        if args.len() > fields.len() || fields[args.len()..].iter().any(|(_, default)| default.is_none()) {
             return Err(Error {
                 msg: ErrorType::IncorrectParameterCount(args.len(), fields.len()),
                 lines: vec![loc],
             });
         }
-
-        let mut m = HashMap::new();
-        for (k, v) in methods.iter() {
-            if let Some(f) = fields.iter().find(|f| f.val == k) {
+        let values = fields
+            .iter()
+            .zip(args)
+            .map(|((f, _), a)| (f.val.clone(), a))
+            .collect::<HashMap<_, _>>();
+        self.instantiate(name, fields, values, methods, loc)
+    }
+    fn struct_literal(
+        &mut self,
+        loc: Location,
+        name: Identifier,
+        fields: Vec<(Identifier, LExpr)>,
+        base: Option<LExpr>,
+    ) -> Result<ValueType, Error> {
+        let struc = self.identifier(name.val.clone(), name.loc)?;
+        let ValueType::Struct(struct_name, struct_fields, methods) = struc else {
+            return Err(Error {
+                msg: ErrorType::ImplNameNotAStruct(name.val),
+                lines: vec![name.loc],
+            });
+        };
+        let mut values = HashMap::new();
+        for (f, expr) in fields {
+            if !struct_fields.iter().any(|(sf, _)| sf.val == f.val) {
+                return Err(Error {
+                    msg: ErrorType::UnknownField(f.val),
+                    lines: vec![f.loc],
+                });
+            }
+            let val = self.visit_expr(expr)?.val;
+            if values.insert(f.val.clone(), val).is_some() {
+                return Err(Error {
+                    msg: ErrorType::DuplicateField(f.val),
+                    lines: vec![f.loc],
+                });
+            }
+        }
+        if let Some(base) = base {
+            let base2 = self.visit_expr(base)?;
+            let ValueType::Instance(_, base_fields, base_methods) = base2.val else {
+                return Err(Error {
+                    msg: ErrorType::ExpectedInstanceOf(struct_name.val),
+                    lines: vec![base2.loc],
+                });
+            };
+            // a different struct may have the same name, but not the same methods
+            if !base_methods.ptr_eq(&methods) {
+                return Err(Error {
+                    msg: ErrorType::ExpectedInstanceOf(struct_name.val),
+                    lines: vec![base2.loc],
+                });
+            }
+            for (f, _) in struct_fields.iter() {
+                if !values.contains_key(&f.val) {
+                    let val = base_fields.get(&f.val).expect("Instance is missing a field").clone();
+                    values.insert(f.val.clone(), val);
+                }
+            }
+        }
+        self.instantiate(struct_name, struct_fields, values, methods, loc)
+    }
+    /// creates the instance from the given field values, using defaults for the missing ones
+    fn instantiate(
+        &mut self,
+        name: Identifier,
+        fields: Vec<(Identifier, Option<ValueType>)>,
+        mut values: HashMap<String, ValueType>,
+        methods: MMap<ValueType>,
+        loc: Location,
+    ) -> Result<ValueType, Error> {
+        for (k, _) in methods.iter() {
+            if let Some((f, _)) = fields.iter().find(|(f, _)| f.val == k) {
                 return Err(Error {
                     msg: ErrorType::DuplicateField(k),
                     lines: vec![f.loc],
                 });
             }
-            m.insert(k, v);
         }
 
-        let m2 = args
-            .iter()
-            .zip(fields)
-            .map(|(a, f)| (f.val, a.clone()))
-            .collect::<HashMap<_, _>>();
-        m.extend(m2);
+        let mut m = HashMap::new();
 
-        Ok(ValueType::Instance(name.val, MMap::new(m)))
+        for (f, default) in fields {
+            let val = match (values.remove(&f.val), default) {
+                (Some(val), _) => val,
+                (None, Some(default)) => self.call_value(Value { val: default, loc }, vec![], loc)?,
+                (None, None) => {
+                    return Err(Error {
+                        msg: ErrorType::MissingField(f.val, name.val),
+                        lines: vec![loc],
+                    })
+                }
+            };
+            m.insert(f.val, val);
+        }
+
+        Ok(ValueType::Instance(name.val, MMap::new(m), methods))
+    }
+}
+
+// generators
+impl Interpreter {
+    fn call_gen_fn(
+        &mut self,
+        params: Vec<String>,
+        body: Vec<LStmt>,
+        mut closure: Closure,
+        args: Vec<ValueType>,
+        loc: Location,
+    ) -> Result<ValueType, Error> {
+        if args.len() != params.len() {
+            return Err(Error {
+                msg: ErrorType::IncorrectParameterCount(args.len(), params.len()),
+                lines: vec![loc],
+            });
+        }
+        closure.push(params.into_iter().zip(args).collect::<HashMap<_, _>>().into());
+        // scope of the body
+        closure.push(HashMap::new().into());
+        Ok(ValueType::Generator(MRef::new(GenState {
+            frames: vec![Frame::Block(body, 0)],
+            scopes: closure,
+            running: false,
+        })))
+    }
+
+    fn generator_method(
+        &mut self,
+        loc: Location,
+        gen: MRef<GenState>,
+        name: Identifier,
+        args: Vec<LExpr>,
+    ) -> Result<ValueType, Error> {
+        if !args.is_empty() {
+            return Err(Error {
+                msg: ErrorType::IncorrectParameterCount(args.len(), 0),
+                lines: vec![loc],
+            });
+        }
+        match name.val.as_str() {
+            "next" => Ok(ValueType::Option(self.resume(gen, loc)?.map(Box::new))),
+            "iter" => Ok(ValueType::Generator(gen)),
+            _ => Err(Error {
+                msg: ErrorType::FieldNotFound(name.val, "generator".to_string()),
+                lines: vec![loc],
+            }),
+        }
+    }
+
+    /// runs the generator until the next yield
+    /// returns None once it has finished
+    fn resume(&mut self, mut gen: MRef<GenState>, loc: Location) -> Result<Option<ValueType>, Error> {
+        // the state is taken out, so it can be worked with freely
+        let mut state = gen.replace(GenState {
+            frames: vec![],
+            scopes: vec![],
+            running: true,
+        });
+        if state.running {
+            return Err(Error {
+                msg: ErrorType::GeneratorRunning,
+                lines: vec![loc],
+            });
+        }
+        let scopes = mem::replace(&mut self.environment.scopes, state.scopes);
+        let res = self.run_frames(&mut state.frames);
+        state.scopes = mem::replace(&mut self.environment.scopes, scopes);
+        if !matches!(res, Ok(Some(_))) {
+            // finished or failed, it cannot continue either way
+            state.frames.clear();
+            state.scopes.clear();
+        }
+        gen.write(state);
+        res.or_else(|err| {
+            let msg = match err.val {
+                InterpErrorType::Error(error) => return Err(error),
+                InterpErrorType::Return(_) => return Ok(None),
+                InterpErrorType::TailCall(_) => unreachable!("Generators do not make tail calls"),
+                InterpErrorType::Throw(val) => ErrorType::UncaughtThrow(val.val),
+                InterpErrorType::Break(..) => ErrorType::BreakOutsideLoop,
+                InterpErrorType::Continue(_) => ErrorType::ContinueOutsideLoop,
+            };
+            Err(Error {
+                msg,
+                lines: vec![err.loc],
+            })
+        })
+    }
+
+    fn run_frames(&mut self, frames: &mut Vec<Frame>) -> Result<Option<ValueType>, InterpError> {
+        while !frames.is_empty() {
+            let err = match self.step(frames) {
+                Ok(Some(val)) => return Ok(Some(val)),
+                Ok(None) => continue,
+                Err(err) => err,
+            };
+            let (InterpErrorType::Break(label, _) | InterpErrorType::Continue(label)) = &err.val else {
+                return Err(err);
+            };
+            let label = label.clone();
+            // unwind to the loop it belongs to
+            loop {
+                match frames.last() {
+                    None => return Err(err),
+                    Some(Frame::Block(..)) => {
+                        frames.pop();
+                        self.remove_scope();
+                    }
+                    Some(Frame::While(.., loop_label) | Frame::For(.., loop_label)) => {
+                        if !targets(&label, loop_label) {
+                            frames.pop();
+                            continue;
+                        }
+                        if matches!(err.val, InterpErrorType::Break(..)) {
+                            frames.pop();
+                        }
+                        break;
+                    }
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// executes a single statement of the innermost frame
+    /// statements containing a yield are pushed as new frames instead
+    fn step(&mut self, frames: &mut Vec<Frame>) -> Result<Option<ValueType>, InterpError> {
+        // the expressions are evaluated here, not in visit_stmt, so loop expressions have to be handled too
+        let res = self.step_frame(frames);
+        self.escaped(res)
+    }
+
+    fn step_frame(&mut self, frames: &mut Vec<Frame>) -> Result<Option<ValueType>, InterpError> {
+        let frame = frames.last_mut().expect("Generator has no frames");
+        match frame {
+            Frame::Block(block, idx) => {
+                let Some(s) = block.get(*idx).cloned() else {
+                    frames.pop();
+                    self.remove_scope();
+                    return Ok(None);
+                };
+                *idx += 1;
+                if !has_yield(&s) {
+                    self.visit_stmt(s)?;
+                    return Ok(None);
+                }
+                match s.val {
+                    Stmt::Yield(expr) => return Ok(Some(self.visit_expr(expr)?.val)),
+                    Stmt::Block(block) => {
+                        self.add_scope();
+                        frames.push(Frame::Block(block, 0));
+                    }
+                    Stmt::If(blocks) => {
+                        for (cond, block) in blocks {
+                            if self.condition(cond)? {
+                                self.add_scope();
+                                frames.push(Frame::Block(block, 0));
+                                break;
+                            }
+                        }
+                    }
+                    Stmt::While(cond, block, label) => frames.push(Frame::While(cond, block, label)),
+                    Stmt::For(ident, expr, block, label) => {
+                        let val = self.visit_expr(expr)?;
+                        frames.push(Frame::For(ident, self.get_iter(val)?, block, label));
+                    }
+                    _ => unreachable!("Only blocks can contain yield"),
+                }
+            }
+            Frame::While(cond, block, _) => {
+                let block = block.clone();
+                if self.condition(cond.clone())? {
+                    self.add_scope();
+                    frames.push(Frame::Block(block, 0));
+                } else {
+                    frames.pop();
+                }
+            }
+            Frame::For(ident, iter, block, _) => {
+                let (name, block) = (ident.val.clone(), block.clone());
+                match self.iter_next(iter, ident.loc)? {
+                    Some(val) => {
+                        self.environment.add_scope_vars(HashMap::from([(name, val)]));
+                        frames.push(Frame::Block(block, 0));
+                    }
+                    None => {
+                        frames.pop();
+                    }
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// lists, strings, generators and instances with either "iter" or "next" methods can be iterated over
+    fn get_iter(&mut self, val: Value) -> Result<Iter, Error> {
+        match val.val {
+            ValueType::List(ls) => Ok(Iter::List(ls, 0)),
+            ValueType::String(s) => Ok(Iter::String(s.chars().collect(), 0)),
+            ValueType::Generator(gen) => Ok(Iter::Generator(gen)),
+            ValueType::Instance(_, _, ref methods) if methods.get(&"iter".to_string()).is_some() => {
+                let iter = methods.get(&"iter".to_string()).unwrap().clone();
+                let iter = self.call_value(Value { val: iter, loc: val.loc }, vec![val.val.clone()], val.loc)?;
+                match iter {
+                    // an iterator does not need to be asked for an iterator again
+                    ValueType::Instance(..) => Ok(Iter::Instance(iter)),
+                    _ => self.get_iter(Value { val: iter, loc: val.loc }),
+                }
+            }
+            ValueType::Instance(_, _, ref methods) if methods.get(&"next".to_string()).is_some() => Ok(Iter::Instance(val.val)),
+            _ => Err(Error {
+                msg: ErrorType::NotIterable(val.val.to_string()),
+                lines: vec![val.loc],
+            }),
+        }
+    }
+
+    fn iter_next(&mut self, iter: &mut Iter, loc: Location) -> Result<Option<ValueType>, Error> {
+        match iter {
+            Iter::List(ls, idx) => {
+                if *idx >= ls.len() {
+                    return Ok(None);
+                }
+                let val = ls.read(|l| l[*idx].val.clone());
+                *idx += 1;
+                Ok(Some(val))
+            }
+            Iter::String(chars, idx) => {
+                let Some(c) = chars.get(*idx) else {
+                    return Ok(None);
+                };
+                *idx += 1;
+                Ok(Some(ValueType::String(c.to_string())))
+            }
+            Iter::Generator(gen) => self.resume(gen.clone(), loc),
+            Iter::Instance(inst) => {
+                let ValueType::Instance(struct_name, _, methods) = inst else {
+                    unreachable!("Only instances are iterated over with next");
+                };
+                let next = methods.get(&"next".to_string()).cloned().ok_or_else(|| Error {
+                    msg: ErrorType::FieldNotFound("next".to_string(), struct_name.clone()),
+                    lines: vec![loc],
+                })?;
+                match self.call_value(Value { val: next, loc }, vec![inst.clone()], loc)? {
+                    ValueType::Option(val) => Ok(val.map(|v| *v)),
+                    val => Err(Error {
+                        msg: ErrorType::InvalidIteratorResult(val.to_string()),
+                        lines: vec![loc],
+                    }),
+                }
+            }
+        }
     }
 }
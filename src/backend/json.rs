@@ -0,0 +1,413 @@
+use std::collections::HashMap;
+
+use super::value::{expect_int, expect_string, int_value, NativeFunction, Value, ValueType};
+use crate::bigint::BigInt;
+use crate::located::Location;
+use crate::mref::MMap;
+
+/// there is no map type, so objects become instances without methods
+/// the name cannot be declared, so it does not belong to any struct
+const OBJECT_NAME: &str = "<object>";
+
+// deeper nesting is most likely not a real document and would overflow the stack
+const MAX_DEPTH: usize = 512;
+
+// a bigger indent is most likely a mistake and could not be allocated anyway
+const MAX_INDENT: usize = 64;
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    depth: usize,
+    // where the values are put, the call to json_parse
+    loc: Location,
+}
+
+impl Parser {
+    fn error(&self, msg: &str) -> String {
+        format!("Invalid JSON at character {}: {msg}", self.pos)
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t' | '\n' | '\r')) {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), String> {
+        if self.peek() != Some(c) {
+            return Err(self.error(&format!("expected \"{c}\"")));
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    /// the whole input has to be a single value
+    fn parse(&mut self) -> Result<ValueType, String> {
+        let val = self.value()?;
+        self.skip_whitespace();
+        if self.peek().is_some() {
+            return Err(self.error("expected the end of input"));
+        }
+        Ok(val)
+    }
+
+    fn value(&mut self) -> Result<ValueType, String> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('{') => self.nested(Self::object),
+            Some('[') => self.nested(Self::list),
+            Some('"') => Ok(ValueType::String(self.string()?)),
+            Some('-' | '0'..='9') => self.number(),
+            Some(c) if c.is_ascii_alphabetic() => self.keyword(),
+            Some(_) => Err(self.error("expected a value")),
+            None => Err(self.error("unexpected end of input")),
+        }
+    }
+
+    fn nested(&mut self, f: fn(&mut Self) -> Result<ValueType, String>) -> Result<ValueType, String> {
+        if self.depth == MAX_DEPTH {
+            return Err(self.error("nested too deep"));
+        }
+        self.depth += 1;
+        let val = f(self);
+        self.depth -= 1;
+        val
+    }
+
+    fn keyword(&mut self) -> Result<ValueType, String> {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_alphabetic()) {
+            self.pos += 1;
+        }
+        match self.chars[start..self.pos].iter().collect::<String>().as_str() {
+            "true" => Ok(ValueType::Bool(true)),
+            "false" => Ok(ValueType::Bool(false)),
+            "null" => Ok(ValueType::Unit),
+            _ => {
+                self.pos = start;
+                Err(self.error("expected a value"))
+            }
+        }
+    }
+
+    fn digits(&mut self) -> Result<(), String> {
+        if !self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            return Err(self.error("expected a digit"));
+        }
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        Ok(())
+    }
+
+    /// integers stay integers of any size, anything with a fraction or an exponent is a float
+    fn number(&mut self) -> Result<ValueType, String> {
+        let start = self.pos;
+        let negative = self.peek() == Some('-');
+        if negative {
+            self.pos += 1;
+        }
+        // no leading zeros
+        if self.peek() == Some('0') {
+            self.pos += 1;
+        } else {
+            self.digits()?;
+        }
+        let int_end = self.pos;
+        let mut is_float = false;
+        if self.peek() == Some('.') {
+            self.pos += 1;
+            self.digits()?;
+            is_float = true;
+        }
+        if matches!(self.peek(), Some('e' | 'E')) {
+            self.pos += 1;
+            if matches!(self.peek(), Some('+' | '-')) {
+                self.pos += 1;
+            }
+            self.digits()?;
+            is_float = true;
+        }
+        let text = self.chars[start..self.pos].iter().collect::<String>();
+        if is_float {
+            return Ok(ValueType::Float(text.parse().unwrap()));
+        }
+        let digits = self.chars[start + negative as usize..int_end].iter().collect::<String>();
+        let n = BigInt::from_decimal(&digits).unwrap();
+        Ok(int_value(if negative { -&n } else { n }))
+    }
+
+    fn hex_escape(&mut self) -> Result<u32, String> {
+        let mut n = 0;
+        for _ in 0..4 {
+            let Some(digit) = self.peek().and_then(|c| c.to_digit(16)) else {
+                return Err(self.error("expected a hexadecimal digit"));
+            };
+            n = n * 16 + digit;
+            self.pos += 1;
+        }
+        Ok(n)
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut s = String::new();
+        loop {
+            let Some(c) = self.peek() else {
+                return Err(self.error("unterminated string"));
+            };
+            self.pos += 1;
+            match c {
+                '"' => return Ok(s),
+                '\\' => {
+                    let Some(escape) = self.peek() else {
+                        return Err(self.error("unterminated string"));
+                    };
+                    self.pos += 1;
+                    s.push(match escape {
+                        '"' => '"',
+                        '\\' => '\\',
+                        '/' => '/',
+                        'b' => '\u{8}',
+                        'f' => '\u{c}',
+                        'n' => '\n',
+                        'r' => '\r',
+                        't' => '\t',
+                        'u' => self.unicode_escape()?,
+                        _ => {
+                            // at the backslash
+                            self.pos -= 2;
+                            return Err(self.error(&format!("invalid escape \"\\{escape}\"")));
+                        }
+                    });
+                }
+                c if (c as u32) < 0x20 => {
+                    self.pos -= 1;
+                    return Err(self.error("control characters have to be escaped"));
+                }
+                c => s.push(c),
+            }
+        }
+    }
+
+    /// characters outside of the basic plane are written as two escaped UTF-16 surrogates
+    fn unicode_escape(&mut self) -> Result<char, String> {
+        let start = self.pos;
+        let first = self.hex_escape()?;
+        let code = match first {
+            0xd800..=0xdbff => {
+                if self.chars.get(self.pos..self.pos + 2) != Some(&['\\', 'u']) {
+                    self.pos = start;
+                    return Err(self.error("unpaired surrogate"));
+                }
+                self.pos += 2;
+                let second = self.hex_escape()?;
+                if !(0xdc00..=0xdfff).contains(&second) {
+                    self.pos = start;
+                    return Err(self.error("unpaired surrogate"));
+                }
+                0x10000 + ((first - 0xd800) << 10) + (second - 0xdc00)
+            }
+            0xdc00..=0xdfff => {
+                self.pos = start;
+                return Err(self.error("unpaired surrogate"));
+            }
+            _ => first,
+        };
+        Ok(char::from_u32(code).unwrap())
+    }
+
+    fn list(&mut self) -> Result<ValueType, String> {
+        self.expect('[')?;
+        let mut items = vec![];
+        self.skip_whitespace();
+        if self.peek() == Some(']') {
+            self.pos += 1;
+            return Ok(ValueType::List(items.into()));
+        }
+        loop {
+            items.push(Value {
+                val: self.value()?,
+                loc: self.loc,
+            });
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') => self.pos += 1,
+                Some(']') => {
+                    self.pos += 1;
+                    return Ok(ValueType::List(items.into()));
+                }
+                _ => return Err(self.error("expected \",\" or \"]\"")),
+            }
+        }
+    }
+
+    /// later duplicate keys replace the earlier ones
+    fn object(&mut self) -> Result<ValueType, String> {
+        self.expect('{')?;
+        let mut fields = HashMap::new();
+        self.skip_whitespace();
+        if self.peek() == Some('}') {
+            self.pos += 1;
+            return Ok(ValueType::Instance(OBJECT_NAME.to_string(), fields.into(), HashMap::new().into()));
+        }
+        loop {
+            self.skip_whitespace();
+            let key = self.string()?;
+            self.skip_whitespace();
+            self.expect(':')?;
+            fields.insert(key, self.value()?);
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') => self.pos += 1,
+                Some('}') => {
+                    self.pos += 1;
+                    return Ok(ValueType::Instance(OBJECT_NAME.to_string(), fields.into(), HashMap::new().into()));
+                }
+                _ => return Err(self.error("expected \",\" or \"}\"")),
+            }
+        }
+    }
+}
+
+struct Writer {
+    indent: usize,
+    out: String,
+    // addresses of the lists and instances being written, to catch cycles
+    seen: Vec<usize>,
+}
+
+impl Writer {
+    fn newline(&mut self, level: usize) -> Result<(), String> {
+        if self.indent > 0 {
+            let width = self.indent.checked_mul(level).ok_or("JSON output would be too long")?;
+            self.out.push('\n');
+            self.out.push_str(&" ".repeat(width));
+        }
+        Ok(())
+    }
+
+    fn string(&mut self, s: &str) {
+        self.out.push('"');
+        for c in s.chars() {
+            match c {
+                '"' => self.out.push_str("\\\""),
+                '\\' => self.out.push_str("\\\\"),
+                '\n' => self.out.push_str("\\n"),
+                '\r' => self.out.push_str("\\r"),
+                '\t' => self.out.push_str("\\t"),
+                c if (c as u32) < 0x20 => self.out.push_str(&format!("\\u{:04x}", c as u32)),
+                c => self.out.push(c),
+            }
+        }
+        self.out.push('"');
+    }
+
+    fn enter(&mut self, addr: usize) -> Result<(), String> {
+        if self.seen.contains(&addr) {
+            return Err("Cannot convert a value containing itself to JSON".to_string());
+        }
+        self.seen.push(addr);
+        Ok(())
+    }
+
+    /// the fields of the instance, sorted so that the output does not change between runs
+    fn fields(map: &MMap<ValueType>) -> Vec<(String, ValueType)> {
+        let mut fields = map.iter().collect::<Vec<_>>();
+        fields.sort_by(|(a, _), (b, _)| a.cmp(b));
+        fields
+    }
+
+    fn value(&mut self, val: &ValueType, level: usize) -> Result<(), String> {
+        match val {
+            ValueType::Unit | ValueType::Option(None) => self.out.push_str("null"),
+            ValueType::Option(Some(val)) => self.value(val, level)?,
+            ValueType::Bool(_) | ValueType::Int(_) | ValueType::BigInt(_) => self.out.push_str(&val.to_string()),
+            ValueType::Float(n) if !n.is_finite() => return Err(format!("Cannot convert {n} to JSON")),
+            ValueType::Float(_) => self.out.push_str(&val.to_string()),
+            ValueType::String(s) => self.string(s),
+            ValueType::List(ls) => {
+                self.enter(ls.addr())?;
+                self.out.push('[');
+                for (i, item) in ls.iter().enumerate() {
+                    if i > 0 {
+                        self.out.push(',');
+                    }
+                    self.newline(level + 1)?;
+                    self.value(&item.val, level + 1)?;
+                }
+                if ls.len() > 0 {
+                    self.newline(level)?;
+                }
+                self.out.push(']');
+                self.seen.pop();
+            }
+            ValueType::Instance(_, map, _) => {
+                self.enter(map.addr())?;
+                let fields = Self::fields(map);
+                self.out.push('{');
+                for (i, (k, v)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        self.out.push(',');
+                    }
+                    self.newline(level + 1)?;
+                    self.string(k);
+                    self.out.push(':');
+                    if self.indent > 0 {
+                        self.out.push(' ');
+                    }
+                    self.value(v, level + 1)?;
+                }
+                if !fields.is_empty() {
+                    self.newline(level)?;
+                }
+                self.out.push('}');
+                self.seen.pop();
+            }
+            ValueType::NativeFunction(_) | ValueType::Function(..) | ValueType::GenFunction(..) => {
+                return Err("Cannot convert a function to JSON".to_string());
+            }
+            _ => return Err(format!("Cannot convert to JSON: {val}")),
+        }
+        Ok(())
+    }
+}
+
+pub const JSON_FUNCS: [(&str, NativeFunction); 2] = [
+    ("json_parse", |interp, args| {
+        let [s] = &args[..] else {
+            return Err(format!("Function takes exactly 1 argument, got: {}", args.len()));
+        };
+        Parser {
+            chars: expect_string(s)?.chars().collect(),
+            pos: 0,
+            depth: 0,
+            loc: interp.native_loc(),
+        }
+        .parse()
+    }),
+    // without the indent or with 0 it is all on a single line
+    ("json_stringify", |_, args| {
+        let (val, indent) = match &args[..] {
+            [val] => (val, 0),
+            [val, indent] => (val, expect_int(indent)?),
+            _ => return Err(format!("Function takes 1 or 2 arguments, got: {}", args.len())),
+        };
+        let indent = usize::try_from(indent).map_err(|_| format!("Indent cannot be negative: {indent}"))?;
+        if indent > MAX_INDENT {
+            return Err(format!("Indent cannot be bigger than {MAX_INDENT}: {indent}"));
+        }
+        let mut writer = Writer {
+            indent,
+            out: String::new(),
+            seen: vec![],
+        };
+        writer.value(val, 0)?;
+        Ok(ValueType::String(writer.out))
+    }),
+];
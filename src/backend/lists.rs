@@ -0,0 +1,216 @@
+use super::generator::Iter;
+use super::interpreter::Interpreter;
+use super::value::{expect_bool, expect_int, expect_list, NativeFunction, Value, ValueType};
+use crate::mref::MList;
+
+fn out_of_range(idx: i64, len: usize) -> String {
+    format!("Index out of range: {idx} (length {len})")
+}
+
+/// the values of the list at the time of calling
+/// the functions called back may change the list, so it should not be read while iterating
+fn items(ls: &MList) -> Vec<Value> {
+    ls.read(Vec::clone)
+}
+
+/// iterates over a list or anything else a for loop accepts, e.g. a generator
+/// lists are copied first, see items
+fn iterate(interp: &mut Interpreter, val: &ValueType) -> Result<Iter, String> {
+    match val {
+        ValueType::List(ls) => Ok(Iter::List(items(ls).into(), 0)),
+        _ => interp.iter(val.clone()),
+    }
+}
+
+/// the list is sorted by merging, which is stable and never assumes the comparison is consistent
+/// the comparison calls moth code, so it can fail
+fn merge_sort(
+    mut items: Vec<Value>,
+    less: &mut impl FnMut(&ValueType, &ValueType) -> Result<bool, String>,
+) -> Result<Vec<Value>, String> {
+    if items.len() <= 1 {
+        return Ok(items);
+    }
+    let right = items.split_off(items.len() / 2);
+    let mut left = merge_sort(items, less)?.into_iter().peekable();
+    let mut right = merge_sort(right, less)?.into_iter().peekable();
+    let mut res = vec![];
+    while let (Some(a), Some(b)) = (left.peek(), right.peek()) {
+        // the left one goes first on ties, which keeps it stable
+        if less(&b.val, &a.val)? {
+            res.push(right.next().unwrap());
+        } else {
+            res.push(left.next().unwrap());
+        }
+    }
+    res.extend(left);
+    res.extend(right);
+    Ok(res)
+}
+
+pub const LIST_FUNCS: [(&str, NativeFunction); 13] = [
+    ("push", |interp, args| {
+        let [ls, val] = &args[..] else {
+            return Err(format!("Function takes exactly 2 arguments, got: {}", args.len()));
+        };
+        expect_list(ls)?.push(Value {
+            val: val.clone(),
+            loc: interp.native_loc(),
+        });
+        Ok(ValueType::Unit)
+    }),
+    ("pop", |_, args| {
+        let [ls] = &args[..] else {
+            return Err(format!("Function takes exactly 1 argument, got: {}", args.len()));
+        };
+        match expect_list(ls)?.pop() {
+            Some(val) => Ok(val.val),
+            None => Err("Cannot pop from an empty list".to_string()),
+        }
+    }),
+    // the index can also be the length, which appends the value
+    ("insert", |interp, args| {
+        let [ls, idx, val] = &args[..] else {
+            return Err(format!("Function takes exactly 3 arguments, got: {}", args.len()));
+        };
+        let mut ls = expect_list(ls)?;
+        let idx = expect_int(idx)?;
+        let len = ls.len();
+        let pos = if idx == len as i64 {
+            len
+        } else {
+            MList::check_index(idx, len).ok_or_else(|| out_of_range(idx, len))?
+        };
+        ls.insert(
+            pos,
+            Value {
+                val: val.clone(),
+                loc: interp.native_loc(),
+            },
+        );
+        Ok(ValueType::Unit)
+    }),
+    ("remove", |_, args| {
+        let [ls, idx] = &args[..] else {
+            return Err(format!("Function takes exactly 2 arguments, got: {}", args.len()));
+        };
+        let mut ls = expect_list(ls)?;
+        let idx = expect_int(idx)?;
+        let pos = MList::check_index(idx, ls.len()).ok_or_else(|| out_of_range(idx, ls.len()))?;
+        Ok(ls.remove(pos).val)
+    }),
+    // the functions up to all take any iterable, the items are taken one at a time
+    ("map", |interp, args| {
+        let [iterable, f] = &args[..] else {
+            return Err(format!("Function takes exactly 2 arguments, got: {}", args.len()));
+        };
+        let loc = interp.native_loc();
+        let mut iter = iterate(interp, iterable)?;
+        let mut res = vec![];
+        while let Some(item) = interp.next_item(&mut iter)? {
+            let val = interp.call_back(f.clone(), vec![item])?;
+            res.push(Value { val, loc });
+        }
+        Ok(ValueType::List(res.into()))
+    }),
+    ("filter", |interp, args| {
+        let [iterable, f] = &args[..] else {
+            return Err(format!("Function takes exactly 2 arguments, got: {}", args.len()));
+        };
+        let loc = interp.native_loc();
+        let mut iter = iterate(interp, iterable)?;
+        let mut res = vec![];
+        while let Some(item) = interp.next_item(&mut iter)? {
+            if expect_bool(interp.call_back(f.clone(), vec![item.clone()])?)? {
+                res.push(Value { val: item, loc });
+            }
+        }
+        Ok(ValueType::List(res.into()))
+    }),
+    // the function gets the accumulator first, then the item
+    ("fold", |interp, args| {
+        let [iterable, init, f] = &args[..] else {
+            return Err(format!("Function takes exactly 3 arguments, got: {}", args.len()));
+        };
+        let mut iter = iterate(interp, iterable)?;
+        let mut acc = init.clone();
+        while let Some(item) = interp.next_item(&mut iter)? {
+            acc = interp.call_back(f.clone(), vec![acc, item])?;
+        }
+        Ok(acc)
+    }),
+    // both stop at the first item which decides the result, leaving the rest of a generator
+    ("any", |interp, args| {
+        let [iterable, f] = &args[..] else {
+            return Err(format!("Function takes exactly 2 arguments, got: {}", args.len()));
+        };
+        let mut iter = iterate(interp, iterable)?;
+        while let Some(item) = interp.next_item(&mut iter)? {
+            if expect_bool(interp.call_back(f.clone(), vec![item])?)? {
+                return Ok(ValueType::Bool(true));
+            }
+        }
+        Ok(ValueType::Bool(false))
+    }),
+    ("all", |interp, args| {
+        let [iterable, f] = &args[..] else {
+            return Err(format!("Function takes exactly 2 arguments, got: {}", args.len()));
+        };
+        let mut iter = iterate(interp, iterable)?;
+        while let Some(item) = interp.next_item(&mut iter)? {
+            if !expect_bool(interp.call_back(f.clone(), vec![item])?)? {
+                return Ok(ValueType::Bool(false));
+            }
+        }
+        Ok(ValueType::Bool(true))
+    }),
+    ("reverse", |_, args| {
+        let [ls] = &args[..] else {
+            return Err(format!("Function takes exactly 1 argument, got: {}", args.len()));
+        };
+        let mut ls = expect_list(ls)?;
+        let mut res = items(&ls);
+        res.reverse();
+        ls.write(res);
+        Ok(ValueType::Unit)
+    }),
+    // values are compared with "==", which instances may overload
+    ("contains", |interp, args| {
+        let [ls, val] = &args[..] else {
+            return Err(format!("Function takes exactly 2 arguments, got: {}", args.len()));
+        };
+        for item in items(&expect_list(ls)?) {
+            if expect_bool(interp.operator("==", item.val, val.clone())?)? {
+                return Ok(ValueType::Bool(true));
+            }
+        }
+        Ok(ValueType::Bool(false))
+    }),
+    ("index_of", |interp, args| {
+        let [ls, val] = &args[..] else {
+            return Err(format!("Function takes exactly 2 arguments, got: {}", args.len()));
+        };
+        for (idx, item) in items(&expect_list(ls)?).into_iter().enumerate() {
+            if expect_bool(interp.operator("==", item.val, val.clone())?)? {
+                return Ok(ValueType::Option(Some(ValueType::Int(idx as i64).into())));
+            }
+        }
+        Ok(ValueType::Option(None))
+    }),
+    // the comparator tells whether the first value goes before the second one
+    // without it, the values are compared with "<", which instances may overload
+    ("sort", |interp, args| {
+        let (ls, cmp) = match &args[..] {
+            [ls] => (ls, None),
+            [ls, cmp] => (ls, Some(cmp)),
+            _ => return Err(format!("Function takes 1 or 2 arguments, got: {}", args.len())),
+        };
+        let mut ls = expect_list(ls)?;
+        let sorted = merge_sort(items(&ls), &mut |a, b| match cmp {
+            Some(cmp) => expect_bool(interp.call_back(cmp.clone(), vec![a.clone(), b.clone()])?),
+            None => expect_bool(interp.operator("<", a.clone(), b.clone())?),
+        })?;
+        ls.write(sorted);
+        Ok(ValueType::Unit)
+    }),
+];
@@ -5,15 +5,16 @@ use std::fmt::Display;
 /// i.e. operators (both unary and binary) are changed into function calls
 /// function and operator declarations are changed into lambdas assigned to their respective
 /// identifier
-use crate::located::Located;
+use crate::{bigint::BigInt, located::Located};
 
 pub type Identifier = Located<String>;
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum Expr {
     Unit,
-    Int(i32),
-    Float(f32),
+    Int(i64),
+    BigInt(BigInt),
+    Float(f64),
     String(String),
     Bool(bool),
     Identifier(String),
@@ -21,8 +22,14 @@ pub enum Expr {
     List(Vec<LExpr>),
     Index(Box<LExpr>, Box<LExpr>), // expr[idx]
     Lambda(Vec<Identifier>, Vec<LStmt>), // |params| { block }
+    GenLambda(Vec<Identifier>, Vec<LStmt>), // generator function, only created from declarations
     FieldAccess(Box<LExpr>, Identifier),
     MethodAccess(Box<LExpr>, Identifier, Vec<LExpr>), // expr.name(args)
+    SafeFieldAccess(Box<LExpr>, Identifier), // expr?.name
+    SafeMethodAccess(Box<LExpr>, Identifier, Vec<LExpr>), // expr?.name(args)
+    Coalesce(Box<LExpr>, Box<LExpr>), // left ?? right, the right one is only evaluated for none
+    StructLiteral(Identifier, Vec<(Identifier, LExpr)>, Option<Box<LExpr>>), // Name { field: expr, ..base }
+    Loop(Vec<LStmt>, Option<Identifier>), // only used as a value, loop statements become while loops
 }
 
 impl Display for Expr {
@@ -30,6 +37,7 @@ impl Display for Expr {
         let s = match self {
             Self::Unit => "()".to_string(),
             Self::Int(n) => n.to_string(),
+            Self::BigInt(n) => n.to_string(),
             Self::Float(n) => n.to_string(),
             Self::String(s) => format!("\"{s}\""),
             Self::Bool(b) => b.to_string(),
@@ -48,11 +56,36 @@ impl Display for Expr {
                 params = params.iter().map(|s| s.to_string()).collect::<Vec<_>>().join(", "),
                 block = block.iter().map(|s| s.to_string()).collect::<Vec<_>>().join("\n")
             ),
+            Self::GenLambda(params, block) => format!(
+                "generator({params}){block}",
+                params = params.iter().map(|s| s.to_string()).collect::<Vec<_>>().join(", "),
+                block = block.iter().map(|s| s.to_string()).collect::<Vec<_>>().join("\n")
+            ),
             Self::FieldAccess(expr, name) => format!("{expr}.{name}"),
             Self::MethodAccess(callee, name, args) => format!(
                 "{callee}.{name}({args})",
                 args = args.iter().map(|e| { format!("{e}") }).collect::<Vec<_>>().join(", ")
             ),
+            Self::SafeFieldAccess(expr, name) => format!("{expr}?.{name}"),
+            Self::SafeMethodAccess(callee, name, args) => format!(
+                "{callee}?.{name}({args})",
+                args = args.iter().map(|e| { format!("{e}") }).collect::<Vec<_>>().join(", ")
+            ),
+            Self::Coalesce(left, right) => format!("({left} ?? {right})"),
+            Self::StructLiteral(name, fields, base) => format!(
+                "{name} {{ {fields}{base} }}",
+                fields = fields
+                    .iter()
+                    .map(|(f, e)| format!("{f}: {e}"))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                base = base.as_ref().map(|b| format!(", ..{b}")).unwrap_or("".to_string())
+            ),
+            Self::Loop(block, label) => format!(
+                "{label}loop {{{block}}}",
+                label = fmt_label(label),
+                block = block.iter().map(|s| s.to_string()).collect::<Vec<_>>().join("\n")
+            ),
         };
         write!(f, "{s}")
     }
@@ -61,6 +94,10 @@ impl Display for Expr {
 
 pub type LExpr = Located<Expr>;
 
+fn fmt_label(label: &Option<Identifier>) -> String {
+    label.as_ref().map(|l| format!("'{l}: ")).unwrap_or("".to_string())
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum Stmt {
     Expr(LExpr),
@@ -69,13 +106,20 @@ pub enum Stmt {
     AssignIndex(LExpr, LExpr, LExpr), // expr[expr] = expr
     Block(Vec<LStmt>),
     If(Vec<(LExpr, Vec<LStmt>)>),
-    While(LExpr, Vec<LStmt>),
+    While(LExpr, Vec<LStmt>, Option<Identifier>), // cond, block, label
     Return(LExpr),
-    Break,
-    Continue,
-    Struct(Identifier, Vec<Identifier>),
+    TailCall(LExpr), // return of a call or a method call, only created in functions
+    Break(Option<Identifier>, Option<LExpr>), // break 'label value;
+    Continue(Option<Identifier>),
+    Struct(Identifier, Vec<(Identifier, Option<LExpr>)>), // name, fields with their defaults
     AssignStruct(LExpr, Identifier, LExpr), // expr.name = expr
-    Impl(Identifier, Vec<LStmt>),
+    Impl(Identifier, Option<Identifier>, Vec<LStmt>), // struct, trait, methods
+    Throw(LExpr),
+    Trait(Identifier, Vec<Identifier>, Vec<LStmt>), // name, required methods, default methods
+    Yield(LExpr),
+    For(Identifier, LExpr, Vec<LStmt>, Option<Identifier>), // for name in expr { block }, label
+    Assert(LExpr, Option<LExpr>), // assert cond, message;
+    Test(Identifier, Vec<LStmt>), // test "name" { block }
 }
 
 impl Display for Stmt {
@@ -108,20 +152,57 @@ impl Display for Stmt {
                     rest = rest.join("")
                 )
             }
-            Self::While(cond, block) => format!(
-                "while {cond} {{{block}}}",
+            Self::While(cond, block, label) => format!(
+                "{label}while {cond} {{{block}}}",
+                label = fmt_label(label),
                 block = block.iter().map(|s| s.to_string()).collect::<Vec<_>>().join("\n")
             ),
             Self::Return(expr) => format!("return {expr};"),
-            Self::Break => "break;".to_string(),
-            Self::Continue => "continue;".to_string(),
+            Self::TailCall(expr) => format!("return {expr};"),
+            Self::Break(label, expr) => format!(
+                "break{label}{expr};",
+                label = label.as_ref().map(|l| format!(" '{l}")).unwrap_or("".to_string()),
+                expr = expr.as_ref().map(|e| format!(" {e}")).unwrap_or("".to_string())
+            ),
+            Self::Continue(label) => format!(
+                "continue{};",
+                label.as_ref().map(|l| format!(" '{l}")).unwrap_or("".to_string())
+            ),
             Self::Struct(name, fields) => format!(
                 "struct {name} {{ {} }}",
-                fields.iter().map(|s| s.to_string()).collect::<Vec<_>>().join(", ")
+                fields
+                    .iter()
+                    .map(|(f, default)| match default {
+                        Some(default) => format!("{f} = {default}"),
+                        None => f.to_string(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ")
             ),
             Self::AssignStruct(expr1, name, expr2) => format!("{expr1}.{} = {expr2}", name.val),
-            Self::Impl(name, block) => format!(
-                "impl {name} {{\n{block}\n}}",
+            Self::Impl(name, trait_name, block) => format!(
+                "impl {trait_name}{name} {{\n{block}\n}}",
+                trait_name = trait_name.as_ref().map(|t| format!("{t} for ")).unwrap_or("".to_string()),
+                block = block.iter().map(|s| s.to_string()).collect::<Vec<_>>().join("\n")
+            ),
+            Self::Throw(expr) => format!("throw {expr};"),
+            Self::Trait(name, required, defaults) => format!(
+                "trait {name} {{\n{required}\n{defaults}\n}}",
+                required = required.iter().map(|s| format!("fun {s};")).collect::<Vec<_>>().join("\n"),
+                defaults = defaults.iter().map(|s| s.to_string()).collect::<Vec<_>>().join("\n")
+            ),
+            Self::Yield(expr) => format!("yield {expr};"),
+            Self::For(ident, expr, block, label) => format!(
+                "{label}for {ident} in {expr} {{{block}}}",
+                label = fmt_label(label),
+                block = block.iter().map(|s| s.to_string()).collect::<Vec<_>>().join("\n")
+            ),
+            Self::Assert(cond, msg) => format!(
+                "assert {cond}{};",
+                msg.as_ref().map(|m| format!(", {m}")).unwrap_or("".to_string())
+            ),
+            Self::Test(name, block) => format!(
+                "test \"{name}\" {{{block}}}",
                 block = block.iter().map(|s| s.to_string()).collect::<Vec<_>>().join("\n")
             ),
         };
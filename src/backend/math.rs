@@ -0,0 +1,161 @@
+use std::f64::consts::{E, PI};
+
+use super::interpreter::Interpreter;
+use super::value::{expect_bool, expect_int, expect_integer, expect_number, int_value, NativeFunction, Value, ValueType};
+use crate::bigint::BigInt;
+
+pub const MATH_VALUES: [(&str, ValueType); 2] = [("pi", ValueType::Float(PI)), ("e", ValueType::Float(E))];
+
+// integer powers whose result would surely have more bits are errors, it is almost 20000 decimal digits
+const MAX_POW_BITS: u64 = 1 << 16;
+
+/// functions on floats, integers are converted
+/// NaN and infinities give the IEEE 754 results, e.g. sin(inf) is NaN
+fn float_fn(args: &[ValueType], f: fn(f64) -> f64) -> Result<ValueType, String> {
+    let [val] = args else {
+        return Err(format!("Function takes exactly 1 argument, got: {}", args.len()));
+    };
+    Ok(ValueType::Float(f(expect_number(val)?)))
+}
+
+/// logarithms are only defined for positive numbers, instead of returning NaN or -inf it is an error
+fn log_fn(args: &[ValueType], f: fn(f64) -> f64) -> Result<ValueType, String> {
+    let [val] = args else {
+        return Err(format!("Function takes exactly 1 argument, got: {}", args.len()));
+    };
+    let n = expect_number(val)?;
+    if n <= 0.0 {
+        return Err(format!("Cannot take the logarithm of a number which is not positive: {val}"));
+    }
+    Ok(ValueType::Float(f(n)))
+}
+
+/// the values are either the arguments or a single list
+/// returns the smallest one, or the largest one when asked for the maximum; the first one wins ties
+/// they are compared with "<", which instances may overload
+fn pick(interp: &mut Interpreter, args: Vec<ValueType>, max: bool) -> Result<ValueType, String> {
+    let vals = match &args[..] {
+        [ValueType::List(ls)] => ls.iter().map(|v| v.val).collect(),
+        _ => args,
+    };
+    let mut vals = vals.into_iter();
+    let Some(mut res) = vals.next() else {
+        return Err("Expected at least one value".to_string());
+    };
+    for val in vals {
+        let (a, b) = if max { (res.clone(), val.clone()) } else { (val.clone(), res.clone()) };
+        if expect_bool(interp.operator("<", a, b)?)? {
+            res = val;
+        }
+    }
+    Ok(res)
+}
+
+fn pow_int(base: &BigInt, exp: i64) -> Result<ValueType, String> {
+    if exp < 0 {
+        return Err(format!("Cannot raise an integer to a negative power: {exp}"));
+    }
+    if let (Some(b), Ok(e)) = (base.to_i64(), u32::try_from(exp)) {
+        if let Some(n) = b.checked_pow(e) {
+            return Ok(ValueType::Int(n));
+        }
+    }
+    // the result has at least this many bits, computing it would take too long or run out of memory
+    if base.bits().saturating_sub(1).saturating_mul(exp as u64) >= MAX_POW_BITS {
+        return Err(format!("Exponent is too big: {exp}"));
+    }
+    // squaring, going through the bits of the exponent
+    let (mut res, mut base, mut exp) = (BigInt::from(1), base.clone(), exp);
+    loop {
+        if exp & 1 == 1 {
+            res = &res * &base;
+        }
+        exp >>= 1;
+        // the square would not be used anymore
+        if exp == 0 {
+            break;
+        }
+        base = &base * &base;
+    }
+    Ok(int_value(res))
+}
+
+pub const MATH_FUNCS: [(&str, NativeFunction); 14] = [
+    ("sqrt", |_, args| {
+        let [val] = &args[..] else {
+            return Err(format!("Function takes exactly 1 argument, got: {}", args.len()));
+        };
+        let n = expect_number(val)?;
+        if n < 0.0 {
+            return Err(format!("Cannot take the square root of a negative number: {val}"));
+        }
+        Ok(ValueType::Float(n.sqrt()))
+    }),
+    ("sin", |_, args| float_fn(&args, f64::sin)),
+    ("cos", |_, args| float_fn(&args, f64::cos)),
+    ("tan", |_, args| float_fn(&args, f64::tan)),
+    ("exp", |_, args| float_fn(&args, f64::exp)),
+    ("ln", |_, args| log_fn(&args, f64::ln)),
+    ("log10", |_, args| log_fn(&args, f64::log10)),
+    ("atan2", |_, args| {
+        let [y, x] = &args[..] else {
+            return Err(format!("Function takes exactly 2 arguments, got: {}", args.len()));
+        };
+        Ok(ValueType::Float(expect_number(y)?.atan2(expect_number(x)?)))
+    }),
+    // integers stay integers, anything else is a float
+    ("pow", |_, args| {
+        let [base, exp] = &args[..] else {
+            return Err(format!("Function takes exactly 2 arguments, got: {}", args.len()));
+        };
+        match (base, exp) {
+            (ValueType::Int(_) | ValueType::BigInt(_), ValueType::Int(_) | ValueType::BigInt(_)) => {
+                let exp = expect_int(exp).map_err(|_| format!("Exponent is too big: {exp}"))?;
+                pow_int(&expect_integer(base)?, exp)
+            }
+            _ => Ok(ValueType::Float(expect_number(base)?.powf(expect_number(exp)?))),
+        }
+    }),
+    ("abs", |_, args| {
+        let [val] = &args[..] else {
+            return Err(format!("Function takes exactly 1 argument, got: {}", args.len()));
+        };
+        match val {
+            ValueType::Float(n) => Ok(ValueType::Float(n.abs())),
+            _ => Ok(int_value(expect_integer(val)?.abs())),
+        }
+    }),
+    ("min", |interp, args| pick(interp, args, false)),
+    ("max", |interp, args| pick(interp, args, true)),
+    // always positive, unless both are zero
+    ("gcd", |_, args| {
+        let [a, b] = &args[..] else {
+            return Err(format!("Function takes exactly 2 arguments, got: {}", args.len()));
+        };
+        let (mut a, mut b) = (expect_integer(a)?.abs(), expect_integer(b)?.abs());
+        while !b.is_zero() {
+            (a, b) = (b.clone(), a.div_rem(&b).1);
+        }
+        Ok(int_value(a))
+    }),
+    // the results of "/" and "%" as a list
+    ("divmod", |interp, args| {
+        let [a, b] = &args[..] else {
+            return Err(format!("Function takes exactly 2 arguments, got: {}", args.len()));
+        };
+        let (a, b) = (expect_integer(a)?, expect_integer(b)?);
+        if b.is_zero() {
+            return Err("Attempted division by zero".to_string());
+        }
+        let (quot, rem) = a.div_rem(&b);
+        Ok(ValueType::List(
+            [quot, rem]
+                .map(|n| Value {
+                    val: int_value(n),
+                    loc: interp.native_loc(),
+                })
+                .to_vec()
+                .into(),
+        ))
+    }),
+];
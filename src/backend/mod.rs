@@ -1,4 +1,14 @@
+pub mod files;
+mod generator;
 pub mod interpreter;
+pub mod json;
+pub mod lists;
+pub mod math;
 mod lowexprstmt;
+pub mod random;
 pub mod simplify;
+pub mod stdin;
+pub mod strings;
+pub mod system;
+pub mod time;
 pub mod value;
@@ -0,0 +1,115 @@
+use std::time::SystemTime;
+
+use super::value::{expect_int, expect_list, NativeFunction, ValueType};
+
+/// xorshift64*, good enough for simulations but not for anything secret
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        // splitmix64 spreads similar seeds apart and never gives the zero state xorshift gets stuck in
+        let mut z = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^= z >> 31;
+        Self {
+            state: if z == 0 { 1 } else { z },
+        }
+    }
+
+    /// seeded by the current time, for when the program does not choose a seed
+    pub fn from_time() -> Self {
+        let nanos = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map_or(0, |d| d.as_nanos());
+        Self::new(nanos as u64)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    /// in [0, 1)
+    pub fn next_f64(&mut self) -> f64 {
+        // the 53 bits which fit into the mantissa
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// in [0, n), the range must not be empty
+    pub fn below(&mut self, n: u64) -> u64 {
+        // values from the incomplete last chunk would make the smaller ones more likely
+        let limit = u64::MAX - u64::MAX % n;
+        loop {
+            let x = self.next_u64();
+            if x < limit {
+                return x % n;
+            }
+        }
+    }
+}
+
+pub const RANDOM_FUNCS: [(&str, NativeFunction); 5] = [
+    ("random", |interp, args| {
+        if !args.is_empty() {
+            return Err(format!("Function takes no arguments, got: {}", args.len()));
+        }
+        Ok(ValueType::Float(interp.rng().next_f64()))
+    }),
+    // both bounds are included
+    ("random_int", |interp, args| {
+        let [lo, hi] = &args[..] else {
+            return Err(format!("Function takes exactly 2 arguments, got: {}", args.len()));
+        };
+        let (lo, hi) = (expect_int(lo)?, expect_int(hi)?);
+        if lo > hi {
+            return Err(format!("Empty range: {lo} to {hi}"));
+        }
+        // the whole range of i64 does not fit into u64 when counted
+        let span = (hi as i128 - lo as i128 + 1) as u128;
+        let offset = match u64::try_from(span) {
+            Ok(span) => interp.rng().below(span),
+            Err(_) => interp.rng().next_u64(),
+        };
+        Ok(ValueType::Int((lo as i128 + offset as i128) as i64))
+    }),
+    // in place
+    ("shuffle", |interp, args| {
+        let [ls] = &args[..] else {
+            return Err(format!("Function takes exactly 1 argument, got: {}", args.len()));
+        };
+        let mut ls = expect_list(ls)?;
+        let mut items = ls.read(Vec::clone);
+        // Fisher-Yates
+        for i in (1..items.len()).rev() {
+            let j = interp.rng().below(i as u64 + 1) as usize;
+            items.swap(i, j);
+        }
+        ls.write(items);
+        Ok(ValueType::Unit)
+    }),
+    ("choice", |interp, args| {
+        let [ls] = &args[..] else {
+            return Err(format!("Function takes exactly 1 argument, got: {}", args.len()));
+        };
+        let ls = expect_list(ls)?;
+        if ls.len() == 0 {
+            return Err("Cannot choose from an empty list".to_string());
+        }
+        let idx = interp.rng().below(ls.len() as u64) as usize;
+        Ok(ls.read(|l| l[idx].val.clone()))
+    }),
+    // the same seed gives the same values in the same program
+    ("seed", |interp, args| {
+        let [n] = &args[..] else {
+            return Err(format!("Function takes exactly 1 argument, got: {}", args.len()));
+        };
+        *interp.rng() = Rng::new(expect_int(n)? as u64);
+        Ok(ValueType::Unit)
+    }),
+];
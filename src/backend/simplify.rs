@@ -1,18 +1,37 @@
+use std::collections::HashMap;
+
 use crate::{
     associativity::Precedence,
-    error::Error,
+    bigint::BigInt,
+    error::{Error, ErrorType},
     exprstmt,
     located::Location,
     visitor::{ExprVisitor, StmtVisitor},
 };
 
-use super::lowexprstmt::{LExpr, Expr, LStmt, Stmt};
+use super::{
+    interpreter::{Capabilities, Interpreter},
+    lowexprstmt::{LExpr, Expr, LStmt, Stmt},
+    value::{NativeFunction, ValueType, NATIVE_FUNCS, NATIVE_OPERATORS},
+};
 
 pub fn simplify(ast: Vec<exprstmt::LStmt>) -> Result<Vec<LStmt>, Error> {
-    Simplifier.simplify(ast)
+    Simplifier {
+        scopes: vec![HashMap::new()],
+        interp: Interpreter::new(HashMap::new(), Capabilities::NONE),
+    }
+    .simplify(ast)
 }
 
-struct Simplifier;
+/// builtin functions besides operators which can be evaluated at compile time
+const CONST_FUNCS: [&str; 2] = ["$$neg", "$$not"];
+
+struct Simplifier {
+    // the value of every constant, anything else declared is None so that it shadows them
+    scopes: Vec<HashMap<String, Option<ValueType>>>,
+    // constant functions never call back, so a blank interpreter shared by all of them is enough
+    interp: Interpreter,
+}
 
 impl Simplifier {
     pub fn simplify(&mut self, ast: Vec<exprstmt::LStmt>) -> Result<Vec<LStmt>, Error> {
@@ -22,6 +41,155 @@ impl Simplifier {
         }
         Ok(ls)
     }
+
+    fn declare(&mut self, name: &str, val: Option<ValueType>) {
+        self.scopes
+            .last_mut()
+            .expect("There is always the global scope")
+            .insert(name.to_string(), val);
+    }
+
+    fn lookup(&self, name: &str) -> Option<&Option<ValueType>> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name))
+    }
+
+    /// lowers the block in a new scope, with the given items (e.g. parameters) already declared
+    fn visit_block(&mut self, block: Vec<exprstmt::LStmt>, items: &[&exprstmt::Identifier]) -> Result<Vec<LStmt>, Error> {
+        self.scopes.push(HashMap::new());
+        for item in items {
+            self.declare(&item.val, None);
+        }
+        let mut bl = vec![];
+        for s in block {
+            bl.push(self.visit_stmt(s)?);
+        }
+        self.scopes.pop();
+        Ok(bl)
+    }
+
+    /// the builtin function the callee refers to, if it can be evaluated at compile time
+    fn const_function(&self, callee: &LExpr) -> Option<NativeFunction> {
+        let Expr::Identifier(name) = &callee.val else {
+            return None;
+        };
+        // the user declared something with the same name
+        if self.lookup(name).is_some() {
+            return None;
+        }
+        NATIVE_OPERATORS
+            .iter()
+            .find(|(op, _, _)| op == name)
+            .map(|(_, _, f)| *f)
+            .or_else(|| {
+                NATIVE_FUNCS
+                    .iter()
+                    .find(|(f, _)| f == name && CONST_FUNCS.contains(f))
+                    .map(|(_, f)| *f)
+            })
+    }
+
+    /// evaluates the expression at compile time
+    /// used both for constants and for folding
+    ///
+    /// if it is not possible, the error points at the offending sub-expression
+    fn eval_const(&mut self, expr: &LExpr) -> Result<ValueType, Error> {
+        let not_constant = |loc| Error {
+            msg: ErrorType::NotConstant,
+            lines: vec![loc],
+        };
+        match &expr.val {
+            Expr::Unit => Ok(ValueType::Unit),
+            Expr::Int(n) => Ok(ValueType::Int(*n)),
+            Expr::BigInt(n) => Ok(ValueType::BigInt(n.clone())),
+            Expr::Float(n) => Ok(ValueType::Float(*n)),
+            Expr::String(s) => Ok(ValueType::String(s.clone())),
+            Expr::Bool(b) => Ok(ValueType::Bool(*b)),
+            Expr::Identifier(name) => match self.lookup(name) {
+                Some(Some(val)) => Ok(val.clone()),
+                _ => Err(not_constant(expr.loc)),
+            },
+            Expr::Call(callee, args) => {
+                let Some(f) = self.const_function(callee) else {
+                    return Err(not_constant(callee.loc));
+                };
+                let mut vals = vec![];
+                for arg in args {
+                    vals.push(self.eval_const(arg)?);
+                }
+                // same as in the interpreter, reported at the operator
+                let val = f(&mut self.interp, vals).map_err(|msg| Error {
+                    msg: ErrorType::NativeFunctionError(msg),
+                    lines: vec![callee.loc],
+                })?;
+                // only values which can be written as a literal can be inlined
+                match to_literal(&val) {
+                    Some(_) => Ok(val),
+                    None => Err(not_constant(expr.loc)),
+                }
+            }
+            _ => Err(not_constant(expr.loc)),
+        }
+    }
+
+    /// replaces a call of a builtin function on literals with its result
+    /// if it fails, it is left to fail at runtime
+    fn fold(&mut self, expr: LExpr) -> LExpr {
+        let Expr::Call(_, args) = &expr.val else {
+            return expr;
+        };
+        if !args.iter().all(|arg| is_literal(&arg.val)) {
+            return expr;
+        }
+        match self.eval_const(&expr).ok().as_ref().and_then(to_literal) {
+            Some(val) => LExpr { val, loc: expr.loc },
+            None => expr,
+        }
+    }
+}
+
+fn to_literal(val: &ValueType) -> Option<Expr> {
+    Some(match val {
+        ValueType::Unit => Expr::Unit,
+        ValueType::Int(n) => Expr::Int(*n),
+        ValueType::BigInt(n) => Expr::BigInt(n.clone()),
+        ValueType::Float(n) => Expr::Float(*n),
+        ValueType::String(s) => Expr::String(s.clone()),
+        ValueType::Bool(b) => Expr::Bool(*b),
+        _ => return None,
+    })
+}
+
+fn is_literal(expr: &Expr) -> bool {
+    matches!(expr, Expr::Unit | Expr::Int(_) | Expr::BigInt(_) | Expr::Float(_) | Expr::String(_) | Expr::Bool(_))
+}
+
+/// whether the call was written as one, operators are lowered to calls of functions named by their symbol
+fn is_written_call(expr: &Expr) -> bool {
+    match expr {
+        Expr::Call(callee, _) => match &callee.val {
+            Expr::Identifier(name) => name.starts_with(|c: char| c.is_alphabetic() || c == '_'),
+            _ => true,
+        },
+        Expr::MethodAccess(..) => true,
+        _ => false,
+    }
+}
+
+/// marks returned calls as tail calls, so that the interpreter can run them without growing the stack
+/// operators are not marked, they are evaluated as usual (and may be overloaded by an instance)
+/// nested functions are handled when they are lowered
+/// generators are deliberately left alone, as they keep their frames between the values they yield
+fn mark_tail_calls(block: &mut [LStmt]) {
+    for s in block {
+        match &mut s.val {
+            Stmt::Return(expr) if is_written_call(&expr.val) => {
+                s.val = Stmt::TailCall(expr.clone());
+            }
+            Stmt::Block(block) | Stmt::While(_, block, _) | Stmt::For(_, _, block, _) => mark_tail_calls(block),
+            Stmt::If(blocks) => blocks.iter_mut().for_each(|(_, block)| mark_tail_calls(block)),
+            _ => {}
+        }
+    }
 }
 
 impl ExprVisitor<LExpr> for Simplifier {
@@ -32,14 +200,21 @@ impl ExprVisitor<LExpr> for Simplifier {
         })
     }
 
-    fn int(&mut self, loc: Location, n: i32) -> Result<LExpr, Error> {
+    fn int(&mut self, loc: Location, n: i64) -> Result<LExpr, Error> {
         Ok(LExpr {
             val: Expr::Int(n),
             loc,
         })
     }
 
-    fn float(&mut self, loc: Location, n: f32) -> Result<LExpr, Error> {
+    fn bigint(&mut self, loc: Location, n: BigInt) -> Result<LExpr, Error> {
+        Ok(LExpr {
+            val: Expr::BigInt(n),
+            loc,
+        })
+    }
+
+    fn float(&mut self, loc: Location, n: f64) -> Result<LExpr, Error> {
         Ok(LExpr {
             val: Expr::Float(n),
             loc,
@@ -61,6 +236,10 @@ impl ExprVisitor<LExpr> for Simplifier {
     }
 
     fn identifier(&mut self, loc: Location, ident: String) -> Result<LExpr, Error> {
+        // constants are inlined
+        if let Some(val) = self.lookup(&ident).cloned().flatten().as_ref().and_then(to_literal) {
+            return Ok(LExpr { val, loc });
+        }
         Ok(LExpr {
             val: Expr::Identifier(ident),
             loc,
@@ -103,10 +282,8 @@ impl ExprVisitor<LExpr> for Simplifier {
         params: Vec<exprstmt::Identifier>,
         body: Vec<exprstmt::LStmt>,
     ) -> Result<LExpr, Error> {
-        let mut bl = vec![];
-        for s in body {
-            bl.push(self.visit_stmt(s)?);
-        }
+        let mut bl = self.visit_block(body, &params.iter().collect::<Vec<_>>())?;
+        mark_tail_calls(&mut bl);
         Ok(LExpr {
             val: Expr::Lambda(params, bl),
             loc,
@@ -121,32 +298,25 @@ impl ExprVisitor<LExpr> for Simplifier {
     // change into a function call
     fn unary(&mut self, loc: Location, op: exprstmt::Symbol, expr: exprstmt::LExpr) -> Result<LExpr, Error> {
         let expr2 = self.visit_expr(expr)?;
-        let val = match &expr2.val {
-            Expr::Int(n) if op.val.as_str() == "-" => Expr::Int(-n),
-            Expr::Float(n) if op.val.as_str() == "-" => Expr::Float(-n),
-            Expr::Bool(b) if op.val.as_str() == "!" => Expr::Bool(!b),
-            _ => {
-                let name = match op.val.as_str() {
-                    "!" => "$$not".to_string(),
-                    "-" => "$$neg".to_string(),
-                    _ => unreachable!(),
-                };
-                return Ok(LExpr {
-                    val: Expr::Call(
-                        LExpr {
-                            val: Expr::Identifier(name),
-                            loc: op.loc,
-                        }
-                        .into(),
-                        vec![expr2],
-                    ),
-                    loc,
-                });
-            }
+        let name = match op.val.as_str() {
+            "!" => "$$not".to_string(),
+            "-" => "$$neg".to_string(),
+            _ => unreachable!(),
         };
-        Ok(LExpr { val, loc })
+        Ok(self.fold(LExpr {
+            val: Expr::Call(
+                LExpr {
+                    val: Expr::Identifier(name),
+                    loc: op.loc,
+                }
+                .into(),
+                vec![expr2],
+            ),
+            loc,
+        }))
     }
 
+    // change into a function call
     fn binary(
         &mut self,
         loc: Location,
@@ -156,76 +326,24 @@ impl ExprVisitor<LExpr> for Simplifier {
     ) -> Result<LExpr, Error> {
         let left2 = self.visit_expr(left)?;
         let right2 = self.visit_expr(right)?;
-
-        // try to fold constant literals
-        match (&left2.val, &right2.val) {
-            (Expr::Int(n1), Expr::Int(n2)) => {
-                let val = match op.val.as_str() {
-                    "+" => n1 + n2,
-                    "-" => n1 - n2,
-                    "*" => n1 * n2,
-                    "/" => n1 / n2,
-                    "%" => n1 % n2,
-                    // it is not a "primitive" operator, cannot be folded
-                    _ => {
-                        return Ok(LExpr {
-                            val: Expr::Call(
-                                LExpr {
-                                    val: Expr::Identifier(op.val),
-                                    loc: op.loc,
-                                }
-                                .into(),
-                                vec![left2, right2],
-                            ),
-                            loc,
-                        })
-                    }
-                };
-                Ok(LExpr {
-                    val: Expr::Int(val),
-                    loc,
-                })
-            }
-            // TODO: try to merge this into int arm
-            (Expr::Float(n1), Expr::Float(n2)) => {
-                let val = match op.val.as_str() {
-                    "+" => n1 + n2,
-                    "-" => n1 - n2,
-                    "*" => n1 * n2,
-                    "/" => n1 / n2,
-                    "%" => n1 % n2,
-                    _ => {
-                        return Ok(LExpr {
-                            val: Expr::Call(
-                                LExpr {
-                                    val: Expr::Identifier(op.val),
-                                    loc: op.loc,
-                                }
-                                .into(),
-                                vec![left2, right2],
-                            ),
-                            loc,
-                        })
-                    }
-                };
-                Ok(LExpr {
-                    val: Expr::Float(val),
-                    loc,
-                })
-            }
-            // arguments are not numbers, cannot be folded
-            _ => Ok(LExpr {
-                val: Expr::Call(
-                    LExpr {
-                        val: Expr::Identifier(op.val),
-                        loc: op.loc,
-                    }
-                    .into(),
-                    vec![left2, right2],
-                ),
+        // the default must not be evaluated when it is not needed, so it cannot be a call
+        if op.val == "??" {
+            return Ok(LExpr {
+                val: Expr::Coalesce(left2.into(), right2.into()),
                 loc,
-            }),
+            });
         }
+        Ok(self.fold(LExpr {
+            val: Expr::Call(
+                LExpr {
+                    val: Expr::Identifier(op.val),
+                    loc: op.loc,
+                }
+                .into(),
+                vec![left2, right2],
+            ),
+            loc,
+        }))
     }
     fn field(&mut self, loc: Location, expr: exprstmt::LExpr, name: exprstmt::Identifier) -> Result<LExpr, Error> {
         Ok(LExpr {
@@ -250,10 +368,67 @@ impl ExprVisitor<LExpr> for Simplifier {
             loc,
         })
     }
+    fn safe_field(&mut self, loc: Location, expr: exprstmt::LExpr, name: exprstmt::Identifier) -> Result<LExpr, Error> {
+        Ok(LExpr {
+            val: Expr::SafeFieldAccess(self.visit_expr(expr)?.into(), name),
+            loc,
+        })
+    }
+    fn safe_method(
+        &mut self,
+        loc: Location,
+        callee: exprstmt::LExpr,
+        name: exprstmt::Identifier,
+        args: Vec<exprstmt::LExpr>,
+    ) -> Result<LExpr, Error> {
+        let callee2 = self.visit_expr(callee)?;
+        let mut ls = vec![];
+        for e in args {
+            ls.push(self.visit_expr(e)?);
+        }
+        Ok(LExpr {
+            val: Expr::SafeMethodAccess(callee2.into(), name, ls),
+            loc,
+        })
+    }
+    fn struct_literal(
+        &mut self,
+        loc: Location,
+        name: exprstmt::Identifier,
+        fields: Vec<(exprstmt::Identifier, exprstmt::LExpr)>,
+        base: Option<exprstmt::LExpr>,
+    ) -> Result<LExpr, Error> {
+        let mut fields2 = vec![];
+        for (f, e) in fields {
+            fields2.push((f, self.visit_expr(e)?));
+        }
+        let base2 = base.map(|b| self.visit_expr(b)).transpose()?;
+        Ok(LExpr {
+            val: Expr::StructLiteral(name, fields2, base2.map(|b| b.into())),
+            loc,
+        })
+    }
+
+    fn loops(&mut self, loc: Location, block: Vec<exprstmt::LStmt>, label: Option<exprstmt::Identifier>) -> Result<LExpr, Error> {
+        let bl = self.visit_block(block, &[])?;
+        Ok(LExpr {
+            val: Expr::Loop(bl, label),
+            loc,
+        })
+    }
 }
 
 impl StmtVisitor<LStmt> for Simplifier {
     fn expr(&mut self, loc: Location, expr: exprstmt::LExpr) -> Result<LStmt, Error> {
+        // the value is not used, so it is the same as "while true"
+        // which also lets generators yield from it
+        if let exprstmt::Expr::Loop(block, label) = expr.val {
+            let cond = exprstmt::LExpr {
+                val: exprstmt::Expr::Bool(true),
+                loc: expr.loc,
+            };
+            return self.whiles(loc, cond, block, label);
+        }
         Ok(LStmt {
             val: Stmt::Expr(self.visit_expr(expr)?),
             loc,
@@ -261,8 +436,10 @@ impl StmtVisitor<LStmt> for Simplifier {
     }
 
     fn var_decl(&mut self, loc: Location, ident: exprstmt::Identifier, expr: exprstmt::LExpr) -> Result<LStmt, Error> {
+        let expr2 = self.visit_expr(expr)?;
+        self.declare(&ident.val, None);
         Ok(LStmt {
-            val: Stmt::VarDecl(ident, self.visit_expr(expr)?),
+            val: Stmt::VarDecl(ident, expr2),
             loc,
         })
     }
@@ -288,10 +465,7 @@ impl StmtVisitor<LStmt> for Simplifier {
     }
 
     fn block(&mut self, loc: Location, block: Vec<exprstmt::LStmt>) -> Result<LStmt, Error> {
-        let mut bl = vec![];
-        for s in block {
-            bl.push(self.visit_stmt(s)?);
-        }
+        let bl = self.visit_block(block, &[])?;
         Ok(LStmt {
             val: Stmt::Block(bl),
             loc,
@@ -301,17 +475,11 @@ impl StmtVisitor<LStmt> for Simplifier {
     fn if_else(&mut self, loc: Location, blocks: Vec<(exprstmt::LExpr, Vec<exprstmt::LStmt>)>, els: Option<Vec<exprstmt::LStmt>>) -> Result<LStmt, Error> {
         let mut bl = vec![];
         for (c, b) in blocks {
-            let mut block = vec![];
-            for s in b {
-                block.push(self.visit_stmt(s)?);
-            }
-            bl.push((self.visit_expr(c)?, block));
+            let cond = self.visit_expr(c)?;
+            bl.push((cond, self.visit_block(b, &[])?));
         }
         if let Some(else_bl) = els {
-            let mut block = vec![];
-            for s in else_bl {
-                block.push(self.visit_stmt(s)?);
-            }
+            let block = self.visit_block(else_bl, &[])?;
             bl.push((LExpr { val: Expr::Bool(true), loc }, block));
         }
         Ok(LStmt {
@@ -320,13 +488,17 @@ impl StmtVisitor<LStmt> for Simplifier {
         })
     }
 
-    fn whiles(&mut self, loc: Location, cond: exprstmt::LExpr, block: Vec<exprstmt::LStmt>) -> Result<LStmt, Error> {
-        let mut bl = vec![];
-        for s in block {
-            bl.push(self.visit_stmt(s)?);
-        }
+    fn whiles(
+        &mut self,
+        loc: Location,
+        cond: exprstmt::LExpr,
+        block: Vec<exprstmt::LStmt>,
+        label: Option<exprstmt::Identifier>,
+    ) -> Result<LStmt, Error> {
+        let cond2 = self.visit_expr(cond)?;
+        let bl = self.visit_block(block, &[])?;
         Ok(LStmt {
-            val: Stmt::While(self.visit_expr(cond)?, bl),
+            val: Stmt::While(cond2, bl, label),
             loc,
         })
     }
@@ -338,16 +510,16 @@ impl StmtVisitor<LStmt> for Simplifier {
         })
     }
 
-    fn brek(&mut self, loc: Location) -> Result<LStmt, Error> {
+    fn brek(&mut self, loc: Location, label: Option<exprstmt::Identifier>, expr: Option<exprstmt::LExpr>) -> Result<LStmt, Error> {
         Ok(LStmt {
-            val: Stmt::Break,
+            val: Stmt::Break(label, expr.map(|e| self.visit_expr(e)).transpose()?),
             loc,
         })
     }
 
-    fn cont(&mut self, loc: Location) -> Result<LStmt, Error> {
+    fn cont(&mut self, loc: Location, label: Option<exprstmt::Identifier>) -> Result<LStmt, Error> {
         Ok(LStmt {
-            val: Stmt::Continue,
+            val: Stmt::Continue(label),
             loc,
         })
     }
@@ -359,10 +531,10 @@ impl StmtVisitor<LStmt> for Simplifier {
         params: Vec<exprstmt::Identifier>,
         block: Vec<exprstmt::LStmt>,
     ) -> Result<LStmt, Error> {
-        let mut bl = vec![];
-        for s in block {
-            bl.push(self.visit_stmt(s)?);
-        }
+        // declared first so that it can call itself
+        self.declare(&name.val, None);
+        let mut bl = self.visit_block(block, &params.iter().collect::<Vec<_>>())?;
+        mark_tail_calls(&mut bl);
         Ok(LStmt {
             val: Stmt::VarDecl(
                 name,
@@ -375,6 +547,27 @@ impl StmtVisitor<LStmt> for Simplifier {
         })
     }
 
+    fn gen_fun(
+        &mut self,
+        loc: Location,
+        name: exprstmt::Identifier,
+        params: Vec<exprstmt::Identifier>,
+        block: Vec<exprstmt::LStmt>,
+    ) -> Result<LStmt, Error> {
+        self.declare(&name.val, None);
+        let bl = self.visit_block(block, &params.iter().collect::<Vec<_>>())?;
+        Ok(LStmt {
+            val: Stmt::VarDecl(
+                name,
+                LExpr {
+                    val: Expr::GenLambda(params, bl),
+                    loc,
+                },
+            ),
+            loc,
+        })
+    }
+
     fn operator(
         &mut self,
         loc: Location,
@@ -390,10 +583,15 @@ impl StmtVisitor<LStmt> for Simplifier {
         &mut self,
         loc: Location,
         name: exprstmt::Identifier,
-        fields: Vec<exprstmt::Identifier>,
+        fields: Vec<(exprstmt::Identifier, Option<exprstmt::LExpr>)>,
     ) -> Result<LStmt, Error> {
+        self.declare(&name.val, None);
+        let mut fields2 = vec![];
+        for (f, default) in fields {
+            fields2.push((f, default.map(|d| self.visit_expr(d)).transpose()?));
+        }
         Ok(LStmt {
-            val: Stmt::Struct(name, fields),
+            val: Stmt::Struct(name, fields2),
             loc,
         })
     }
@@ -410,13 +608,83 @@ impl StmtVisitor<LStmt> for Simplifier {
             loc,
         })
     }
-    fn imp(&mut self, loc: Location, name: exprstmt::Identifier, block: Vec<exprstmt::LStmt>) -> Result<LStmt, Error> {
-        let mut block2 = vec![];
-        for s in block {
-            block2.push(self.visit_stmt(s)?);
-        }
+    fn imp(
+        &mut self,
+        loc: Location,
+        name: exprstmt::Identifier,
+        trait_name: Option<exprstmt::Identifier>,
+        block: Vec<exprstmt::LStmt>,
+    ) -> Result<LStmt, Error> {
+        // methods are not accessible by their names alone, so they get their own scope
+        let block2 = self.visit_block(block, &[])?;
+        Ok(LStmt {
+            val: Stmt::Impl(name, trait_name, block2),
+            loc,
+        })
+    }
+    fn trai(
+        &mut self,
+        loc: Location,
+        name: exprstmt::Identifier,
+        required: Vec<(exprstmt::Identifier, Vec<exprstmt::Identifier>)>,
+        defaults: Vec<exprstmt::LStmt>,
+    ) -> Result<LStmt, Error> {
+        // the parameters are checked in varcheck, only the names are needed now
+        let required2 = required.into_iter().map(|(name, _)| name).collect();
+        self.declare(&name.val, None);
+        let defaults2 = self.visit_block(defaults, &[])?;
+        Ok(LStmt {
+            val: Stmt::Trait(name, required2, defaults2),
+            loc,
+        })
+    }
+    fn yiel(&mut self, loc: Location, expr: exprstmt::LExpr) -> Result<LStmt, Error> {
+        Ok(LStmt {
+            val: Stmt::Yield(self.visit_expr(expr)?),
+            loc,
+        })
+    }
+    fn fors(
+        &mut self,
+        loc: Location,
+        ident: exprstmt::Identifier,
+        expr: exprstmt::LExpr,
+        block: Vec<exprstmt::LStmt>,
+        label: Option<exprstmt::Identifier>,
+    ) -> Result<LStmt, Error> {
+        let expr2 = self.visit_expr(expr)?;
+        let bl = self.visit_block(block, &[&ident])?;
+        Ok(LStmt {
+            val: Stmt::For(ident, expr2, bl, label),
+            loc,
+        })
+    }
+    // the value is computed now and inlined wherever the constant is used
+    // the declaration is kept for constants in impls, which are accessed as fields
+    fn cons(&mut self, loc: Location, ident: exprstmt::Identifier, expr: exprstmt::LExpr) -> Result<LStmt, Error> {
+        let expr2 = self.visit_expr(expr)?;
+        let val = self.eval_const(&expr2)?;
+        self.declare(&ident.val, Some(val));
+        Ok(LStmt {
+            val: Stmt::VarDecl(ident, expr2),
+            loc,
+        })
+    }
+    fn throw(&mut self, loc: Location, expr: exprstmt::LExpr) -> Result<LStmt, Error> {
+        Ok(LStmt {
+            val: Stmt::Throw(self.visit_expr(expr)?),
+            loc,
+        })
+    }
+    fn asser(&mut self, loc: Location, cond: exprstmt::LExpr, msg: Option<exprstmt::LExpr>) -> Result<LStmt, Error> {
+        Ok(LStmt {
+            val: Stmt::Assert(self.visit_expr(cond)?, msg.map(|m| self.visit_expr(m)).transpose()?),
+            loc,
+        })
+    }
+    fn test(&mut self, loc: Location, name: exprstmt::Identifier, block: Vec<exprstmt::LStmt>) -> Result<LStmt, Error> {
         Ok(LStmt {
-            val: Stmt::Impl(name, block2),
+            val: Stmt::Test(name, self.visit_block(block, &[])?),
             loc,
         })
     }
@@ -0,0 +1,38 @@
+use std::io::{self, Write};
+
+use super::value::{expect_string, NativeFunction, ValueType};
+
+fn read_error(err: io::Error) -> String {
+    format!("Cannot read input: {err}")
+}
+
+// the end of input is none, so that it is not confused with an empty line
+pub const STDIN_FUNCS: [(&str, NativeFunction); 3] = [
+    // the prompt is shown without a newline
+    ("input", |interp, args| {
+        match &args[..] {
+            [] => {}
+            [prompt] => {
+                print!("{}", expect_string(prompt)?);
+                io::stdout().flush().map_err(|err| format!("Cannot show the prompt: {err}"))?;
+            }
+            _ => return Err(format!("Function takes 0 or 1 arguments, got: {}", args.len())),
+        }
+        let line = interp.read_line().map_err(read_error)?;
+        Ok(ValueType::Option(line.map(|s| ValueType::String(s).into())))
+    }),
+    ("read_line", |interp, args| {
+        if !args.is_empty() {
+            return Err(format!("Function takes no arguments, got: {}", args.len()));
+        }
+        let line = interp.read_line().map_err(read_error)?;
+        Ok(ValueType::Option(line.map(|s| ValueType::String(s).into())))
+    }),
+    // an empty string at the end of input
+    ("read_all", |interp, args| {
+        if !args.is_empty() {
+            return Err(format!("Function takes no arguments, got: {}", args.len()));
+        }
+        Ok(ValueType::String(interp.read_all().map_err(read_error)?))
+    }),
+];
@@ -0,0 +1,139 @@
+use super::value::{expect_int, expect_list, expect_string, int_value, string_list, NativeFunction, ValueType};
+use crate::bigint::BigInt;
+
+// the longest string repeat can make, in bytes
+const MAX_REPEAT_LENGTH: usize = 1 << 30;
+
+/// an optional sign followed by digits, which may be too big for i64
+fn parse_int(s: &str) -> Option<ValueType> {
+    if let Ok(n) = s.parse::<i64>() {
+        return Some(ValueType::Int(n));
+    }
+    let (negative, digits) = match s.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, s.strip_prefix('+').unwrap_or(s)),
+    };
+    let n = BigInt::from_decimal(digits)?;
+    Some(int_value(if negative { -&n } else { n }))
+}
+
+// all of them work with characters, so indexes and lengths are never in bytes
+pub const STRING_FUNCS: [(&str, NativeFunction); 13] = [
+    // without a separator it splits on whitespace, leaving out empty parts
+    ("split", |interp, args| {
+        let loc = interp.native_loc();
+        match &args[..] {
+            [s] => Ok(string_list(expect_string(s)?.split_whitespace().map(str::to_string), loc)),
+            [s, sep] => {
+                let sep = expect_string(sep)?;
+                if sep.is_empty() {
+                    return Err("Separator cannot be empty".to_string());
+                }
+                Ok(string_list(expect_string(s)?.split(sep).map(str::to_string), loc))
+            }
+            _ => Err(format!("Function takes 1 or 2 arguments, got: {}", args.len())),
+        }
+    }),
+    ("join", |_, args| {
+        let [ls, sep] = &args[..] else {
+            return Err(format!("Function takes exactly 2 arguments, got: {}", args.len()));
+        };
+        let sep = expect_string(sep)?;
+        let mut parts = vec![];
+        for item in expect_list(ls)?.iter() {
+            parts.push(expect_string(&item.val)?.to_string());
+        }
+        Ok(ValueType::String(parts.join(sep)))
+    }),
+    ("trim", |_, args| {
+        let [s] = &args[..] else {
+            return Err(format!("Function takes exactly 1 argument, got: {}", args.len()));
+        };
+        Ok(ValueType::String(expect_string(s)?.trim().to_string()))
+    }),
+    ("upper", |_, args| {
+        let [s] = &args[..] else {
+            return Err(format!("Function takes exactly 1 argument, got: {}", args.len()));
+        };
+        Ok(ValueType::String(expect_string(s)?.to_uppercase()))
+    }),
+    ("lower", |_, args| {
+        let [s] = &args[..] else {
+            return Err(format!("Function takes exactly 1 argument, got: {}", args.len()));
+        };
+        Ok(ValueType::String(expect_string(s)?.to_lowercase()))
+    }),
+    ("starts_with", |_, args| {
+        let [s, prefix] = &args[..] else {
+            return Err(format!("Function takes exactly 2 arguments, got: {}", args.len()));
+        };
+        Ok(ValueType::Bool(expect_string(s)?.starts_with(expect_string(prefix)?)))
+    }),
+    ("ends_with", |_, args| {
+        let [s, suffix] = &args[..] else {
+            return Err(format!("Function takes exactly 2 arguments, got: {}", args.len()));
+        };
+        Ok(ValueType::Bool(expect_string(s)?.ends_with(expect_string(suffix)?)))
+    }),
+    // the index of the first occurrence as an option
+    ("find", |_, args| {
+        let [s, sub] = &args[..] else {
+            return Err(format!("Function takes exactly 2 arguments, got: {}", args.len()));
+        };
+        let s = expect_string(s)?;
+        Ok(ValueType::Option(
+            s.find(expect_string(sub)?)
+                .map(|byte| ValueType::Int(s[..byte].chars().count() as i64).into()),
+        ))
+    }),
+    ("replace", |_, args| {
+        let [s, from, to] = &args[..] else {
+            return Err(format!("Function takes exactly 3 arguments, got: {}", args.len()));
+        };
+        Ok(ValueType::String(
+            expect_string(s)?.replace(expect_string(from)?, expect_string(to)?),
+        ))
+    }),
+    ("repeat", |_, args| {
+        let [s, n] = &args[..] else {
+            return Err(format!("Function takes exactly 2 arguments, got: {}", args.len()));
+        };
+        let s = expect_string(s)?;
+        let n = expect_int(n)?;
+        if n < 0 {
+            return Err(format!("Cannot repeat a string a negative number of times: {n}"));
+        }
+        // anything longer would most likely not fit into memory, which aborts the whole process
+        match s.len().checked_mul(n as usize) {
+            Some(len) if len <= MAX_REPEAT_LENGTH => Ok(ValueType::String(s.repeat(n as usize))),
+            _ => Err(format!("Repeated string would be too long: {n} times")),
+        }
+    }),
+    ("chars", |interp, args| {
+        let [s] = &args[..] else {
+            return Err(format!("Function takes exactly 1 argument, got: {}", args.len()));
+        };
+        Ok(string_list(
+            expect_string(s)?.chars().map(|c| c.to_string()),
+            interp.native_loc(),
+        ))
+    }),
+    // the parsing functions return none for invalid input
+    ("parse_int", |_, args| {
+        let [s] = &args[..] else {
+            return Err(format!("Function takes exactly 1 argument, got: {}", args.len()));
+        };
+        Ok(ValueType::Option(parse_int(expect_string(s)?).map(Box::new)))
+    }),
+    ("parse_float", |_, args| {
+        let [s] = &args[..] else {
+            return Err(format!("Function takes exactly 1 argument, got: {}", args.len()));
+        };
+        let s = expect_string(s)?;
+        // Rust also accepts "nan", "inf" and "infinity", which are not numbers in moth
+        if !s.chars().any(|c| c.is_ascii_digit()) {
+            return Ok(ValueType::Option(None));
+        }
+        Ok(ValueType::Option(s.parse::<f64>().ok().map(|n| ValueType::Float(n).into())))
+    }),
+];
@@ -0,0 +1,59 @@
+use std::env;
+
+use super::value::{expect_int, expect_string, string_list, NativeFunction, ValueType};
+
+/// names which would make setting the variable panic
+fn check_env_name(name: &str) -> Result<(), String> {
+    if name.is_empty() || name.contains(['=', '\0']) {
+        return Err(format!("Invalid environment variable name: \"{name}\""));
+    }
+    Ok(())
+}
+
+pub const SYSTEM_FUNCS: [(&str, NativeFunction); 4] = [
+    // the arguments after the name of the script
+    ("args", |interp, args| {
+        if !args.is_empty() {
+            return Err(format!("Function takes no arguments, got: {}", args.len()));
+        }
+        Ok(string_list(interp.args().iter().cloned(), interp.native_loc()))
+    }),
+    // the value as an option
+    ("env", |_, args| {
+        let [name] = &args[..] else {
+            return Err(format!("Function takes exactly 1 argument, got: {}", args.len()));
+        };
+        let name = expect_string(name)?;
+        check_env_name(name)?;
+        Ok(ValueType::Option(
+            env::var(name).ok().map(|val| ValueType::String(val).into()),
+        ))
+    }),
+    ("set_env", |_, args| {
+        let [name, val] = &args[..] else {
+            return Err(format!("Function takes exactly 2 arguments, got: {}", args.len()));
+        };
+        let (name, val) = (expect_string(name)?, expect_string(val)?);
+        check_env_name(name)?;
+        if val.contains('\0') {
+            return Err("Environment variable value cannot contain a null character".to_string());
+        }
+        // the interpreter does not run any other threads
+        unsafe {
+            env::set_var(name, val);
+        }
+        Ok(ValueType::Unit)
+    }),
+    // the program ends once everything unwinds, which is more graceful than ending the process here
+    ("exit", |interp, args| {
+        let code = match &args[..] {
+            [] => 0,
+            [code] => {
+                let code = expect_int(code)?;
+                i32::try_from(code).map_err(|_| format!("Exit code out of range: {code}"))?
+            }
+            _ => return Err(format!("Function takes 0 or 1 arguments, got: {}", args.len())),
+        };
+        Err(interp.exit(code))
+    }),
+];
@@ -0,0 +1,121 @@
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use super::value::{expect_int, expect_string, int_value, NativeFunction, ValueType};
+use crate::bigint::BigInt;
+
+const DAY_NAMES: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+const MONTH_NAMES: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+fn since_epoch() -> Duration {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+}
+
+/// the date of the day counted from 1970-01-01 as (year, month, day)
+/// see https://howardhinnant.github.io/date_algorithms.html#civil_from_days
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    // days are counted from 0000-03-01, so that the leap day is the last one of the year
+    let z = days + 719_468;
+    // 400 years always have the same number of days
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+    (year, month, day)
+}
+
+fn is_leap(year: i64) -> bool {
+    year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+}
+
+/// formats seconds since the epoch in UTC
+fn format_time(epoch: i64, fmt: &str) -> Result<String, String> {
+    let days = epoch.div_euclid(86400);
+    let secs = epoch.rem_euclid(86400);
+    let (year, month, day) = civil_from_days(days);
+    let day_of_year = {
+        const BEFORE_MONTH: [u32; 12] = [0, 31, 59, 90, 120, 151, 181, 212, 243, 273, 304, 334];
+        BEFORE_MONTH[month as usize - 1] + day + (month > 2 && is_leap(year)) as u32
+    };
+    // 1970-01-01 was a Thursday
+    let weekday = (days + 3).rem_euclid(7) as usize;
+
+    let mut res = String::new();
+    let mut chars = fmt.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            res.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('Y') => res += &format!("{year:04}"),
+            Some('m') => res += &format!("{month:02}"),
+            Some('d') => res += &format!("{day:02}"),
+            Some('H') => res += &format!("{:02}", secs / 3600),
+            Some('M') => res += &format!("{:02}", secs / 60 % 60),
+            Some('S') => res += &format!("{:02}", secs % 60),
+            Some('j') => res += &format!("{day_of_year:03}"),
+            Some('a') => res += DAY_NAMES[weekday],
+            Some('b') => res += MONTH_NAMES[month as usize - 1],
+            Some('%') => res.push('%'),
+            Some(c) => return Err(format!("Unknown format specifier: %{c}")),
+            None => return Err("Format ends with a lone %".to_string()),
+        }
+    }
+    Ok(res)
+}
+
+pub const TIME_FUNCS: [(&str, NativeFunction); 5] = [
+    // milliseconds since the epoch
+    ("now_ms", |_, args| {
+        if !args.is_empty() {
+            return Err(format!("Function takes no arguments, got: {}", args.len()));
+        }
+        Ok(ValueType::Int(since_epoch().as_millis() as i64))
+    }),
+    // nanoseconds since the epoch, which stop fitting into i64 in 2262
+    ("now_ns", |_, args| {
+        if !args.is_empty() {
+            return Err(format!("Function takes no arguments, got: {}", args.len()));
+        }
+        let nanos = since_epoch().as_nanos();
+        Ok(match i64::try_from(nanos) {
+            Ok(n) => ValueType::Int(n),
+            Err(_) => int_value(BigInt::from_decimal(&nanos.to_string()).unwrap()),
+        })
+    }),
+    // seconds since the program started as a float
+    // unlike the other ones it never goes backwards, so it is the one for measuring
+    ("clock", |interp, args| {
+        if !args.is_empty() {
+            return Err(format!("Function takes no arguments, got: {}", args.len()));
+        }
+        Ok(ValueType::Float(interp.started().elapsed().as_secs_f64()))
+    }),
+    ("sleep", |_, args| {
+        let [ms] = &args[..] else {
+            return Err(format!("Function takes exactly 1 argument, got: {}", args.len()));
+        };
+        let ms = expect_int(ms)?;
+        let ms = u64::try_from(ms).map_err(|_| format!("Cannot sleep for a negative time: {ms}"))?;
+        thread::sleep(Duration::from_millis(ms));
+        Ok(ValueType::Unit)
+    }),
+    // the seconds since the epoch as a date in UTC
+    ("format_time", |_, args| {
+        let (epoch, fmt) = match &args[..] {
+            [epoch] => (epoch, "%Y-%m-%d %H:%M:%S"),
+            [epoch, fmt] => (epoch, expect_string(fmt)?),
+            _ => return Err(format!("Function takes 1 or 2 arguments, got: {}", args.len())),
+        };
+        Ok(ValueType::String(format_time(expect_int(epoch)?, fmt)?))
+    }),
+];
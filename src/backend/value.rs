@@ -1,32 +1,56 @@
+use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::fmt::Display;
 use std::time::SystemTime;
 
+use super::files::FILE_FUNCS;
+use super::generator::GenState;
+use super::interpreter::Interpreter;
+use super::json::JSON_FUNCS;
+use super::lists::LIST_FUNCS;
+use super::math::{MATH_FUNCS, MATH_VALUES};
+use super::random::RANDOM_FUNCS;
+use super::stdin::STDIN_FUNCS;
+use super::strings::STRING_FUNCS;
+use super::system::SYSTEM_FUNCS;
+use super::time::TIME_FUNCS;
 use super::lowexprstmt::{Identifier, LStmt};
 use crate::associativity::{Associativity, Precedence};
-use crate::located::Located;
-use crate::mref::{MList, MMap};
+use crate::bigint::BigInt;
+use crate::located::{Located, Location};
+use crate::mref::{MList, MMap, MRef};
 
-pub type NativeFunction = fn(Vec<ValueType>) -> Result<ValueType, String>;
+/// natives get the interpreter, so that they can call values passed to them, see Interpreter::call_back
+pub type NativeFunction = fn(&mut Interpreter, Vec<ValueType>) -> Result<ValueType, String>;
 pub type Closure = Vec<MMap<ValueType>>;
 
+// natives are compared by address, which is good enough for our purposes
+#[allow(unpredictable_function_pointer_comparisons)]
 #[derive(Debug, Clone, PartialEq)]
 pub enum ValueType {
     String(String),
     Bool(bool),
-    Int(i32),
-    Float(f32),
+    Int(i64),
+    BigInt(BigInt), // only for integers which do not fit into Int
+    Float(f64),
     List(MList),
     NativeFunction(NativeFunction),
     Function(Vec<String>, Vec<LStmt>, Closure), // fn(params) { block }, closure
-    Struct(Identifier, Vec<Identifier>, MMap<ValueType>), // name, fields, methods
-    Instance(String, MMap<ValueType>),
+    GenFunction(Vec<String>, Vec<LStmt>, Closure), // same as function, calling it creates a generator
+    Generator(MRef<GenState>),
+    Struct(Identifier, Vec<(Identifier, Option<ValueType>)>, MMap<ValueType>), // name, fields with defaults, methods
+    Instance(String, MMap<ValueType>, MMap<ValueType>), // struct name, fields, methods shared with the struct
+    Trait(Identifier, Vec<Identifier>, MMap<ValueType>), // name, required methods, default methods
+    Option(Option<Box<ValueType>>), // none, some(val)
     Unit,
 }
 impl Display for ValueType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let s = match self {
             Self::Int(n) => n.to_string(),
+            Self::BigInt(n) => n.to_string(),
+            // keep the decimal point, so that it is read back as a float
+            Self::Float(n) if n.is_finite() && n.fract() == 0.0 => format!("{n:.1}"),
             Self::Float(n) => n.to_string(),
             Self::Bool(b) => b.to_string(),
             Self::String(s) => format!("\"{s}\""),
@@ -44,12 +68,29 @@ impl Display for ValueType {
                 params.join(", "),
                 body.iter().map(|s| format!("{s}")).collect::<Vec<_>>().join(", ")
             ),
+            Self::GenFunction(params, body, _) => format!(
+                "fun*({}) {{ {} }}",
+                params.join(", "),
+                body.iter().map(|s| format!("{s}")).collect::<Vec<_>>().join(", ")
+            ),
+            Self::Generator(_) => "<generator>".to_string(),
+            Self::Trait(name, required, defaults) => format!(
+                "trait {name} {{ {} }}",
+                required
+                    .iter()
+                    .map(|s| s.val.clone())
+                    .chain(defaults.iter().map(|(k, _)| k))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            Self::Option(None) => "none".to_string(),
+            Self::Option(Some(val)) => format!("some({val})"),
             Self::Unit => "()".to_string(),
             Self::Struct(name, fields, _) => format!(
                 "struct {name} {{ {} }}",
-                fields.iter().map(|s| s.to_string()).collect::<Vec<_>>().join(", ")
+                fields.iter().map(|(s, _)| s.to_string()).collect::<Vec<_>>().join(", ")
             ),
-            Self::Instance(name, map) => format!(
+            Self::Instance(name, map, _) => format!(
                 "{}({})",
                 name,
                 map.read(|m| format!(
@@ -65,8 +106,239 @@ impl Display for ValueType {
     }
 }
 
+impl ValueType {
+    /// identity; values which are not references (numbers, strings...) are compared by value
+    pub fn is(&self, other: &ValueType) -> bool {
+        match (self, other) {
+            (Self::List(a), Self::List(b)) => a.ptr_eq(b),
+            (Self::Instance(_, a, _), Self::Instance(_, b, _)) => a.ptr_eq(b),
+            (Self::Struct(_, _, a), Self::Struct(_, _, b)) => a.ptr_eq(b),
+            (Self::Trait(_, _, a), Self::Trait(_, _, b)) => a.ptr_eq(b),
+            (Self::Generator(a), Self::Generator(b)) => a.ptr_eq(b),
+            (Self::Function(params1, body1, closure1), Self::Function(params2, body2, closure2))
+            | (Self::GenFunction(params1, body1, closure1), Self::GenFunction(params2, body2, closure2)) => {
+                closure1.len() == closure2.len()
+                    && closure1.iter().zip(closure2).all(|(a, b)| a.ptr_eq(b))
+                    && params1 == params2
+                    && body1 == body2
+            }
+            (Self::Option(Some(a)), Self::Option(Some(b))) => a.is(b),
+            _ => self.equals(other),
+        }
+    }
+
+    /// structural equality, values of different kinds are never equal
+    pub fn equals(&self, other: &ValueType) -> bool {
+        self.equals_inner(other, &mut vec![])
+    }
+    /// pairs already being compared are assumed to be equal, which makes cycles terminate
+    fn equals_inner(&self, other: &ValueType, seen: &mut Vec<(usize, usize)>) -> bool {
+        match (self, other) {
+            (Self::String(a), Self::String(b)) => a == b,
+            (Self::Bool(a), Self::Bool(b)) => a == b,
+            (Self::Int(a), Self::Int(b)) => a == b,
+            (Self::BigInt(a), Self::BigInt(b)) => a == b,
+            (Self::Float(a), Self::Float(b)) => a == b,
+            (Self::Unit, Self::Unit) => true,
+            (Self::Option(None), Self::Option(None)) => true,
+            (Self::Option(Some(a)), Self::Option(Some(b))) => a.equals_inner(b, seen),
+            (Self::List(a), Self::List(b)) => {
+                let pair = (a.addr(), b.addr());
+                if a.ptr_eq(b) || seen.contains(&pair) {
+                    return true;
+                }
+                if a.len() != b.len() {
+                    return false;
+                }
+                seen.push(pair);
+                let res = a.iter().zip(b.iter()).all(|(x, y)| x.val.equals_inner(&y.val, seen));
+                seen.pop();
+                res
+            }
+            (Self::Instance(name1, a, _), Self::Instance(name2, b, _)) => {
+                let pair = (a.addr(), b.addr());
+                if a.ptr_eq(b) || seen.contains(&pair) {
+                    return true;
+                }
+                if name1 != name2 || a.read(HashMap::len) != b.read(HashMap::len) {
+                    return false;
+                }
+                seen.push(pair);
+                let res = a
+                    .iter()
+                    .all(|(k, x)| b.get(&k).is_some_and(|y| x.equals_inner(y, seen)));
+                seen.pop();
+                res
+            }
+            (Self::NativeFunction(_), Self::NativeFunction(_))
+            | (Self::Function(..), Self::Function(..))
+            | (Self::GenFunction(..), Self::GenFunction(..))
+            | (Self::Generator(_), Self::Generator(_))
+            | (Self::Struct(..), Self::Struct(..))
+            | (Self::Trait(..), Self::Trait(..)) => self.is(other),
+            _ => false,
+        }
+    }
+
+    /// ordering of numbers, bools, and lexicographic ordering of strings and lists
+    /// returns Ok(None) if the values are unordered (NaN)
+    pub fn compare(&self, other: &ValueType) -> Result<Option<Ordering>, String> {
+        self.compare_inner(other, &mut vec![])
+    }
+    fn compare_inner(&self, other: &ValueType, seen: &mut Vec<(usize, usize)>) -> Result<Option<Ordering>, String> {
+        Ok(match (self, other) {
+            (Self::Int(a), Self::Int(b)) => a.partial_cmp(b),
+            (Self::Int(_) | Self::BigInt(_), Self::Int(_) | Self::BigInt(_)) => {
+                // both are integers, checked above
+                Some(to_bigint(self).unwrap().cmp(&to_bigint(other).unwrap()))
+            }
+            (Self::Float(a), Self::Float(b)) => a.partial_cmp(b),
+            (Self::Bool(a), Self::Bool(b)) => a.partial_cmp(b),
+            (Self::String(a), Self::String(b)) => a.partial_cmp(b),
+            (Self::List(a), Self::List(b)) => {
+                let pair = (a.addr(), b.addr());
+                if a.ptr_eq(b) || seen.contains(&pair) {
+                    return Ok(Some(Ordering::Equal));
+                }
+                seen.push(pair);
+                for (x, y) in a.iter().zip(b.iter()) {
+                    match x.val.compare_inner(&y.val, seen) {
+                        Ok(Some(Ordering::Equal)) => {}
+                        res => {
+                            seen.pop();
+                            return res;
+                        }
+                    }
+                }
+                seen.pop();
+                a.len().partial_cmp(&b.len())
+            }
+            _ => return Err(format!("Invalid values: \"{self}\" and \"{other}\"")),
+        })
+    }
+}
+
 pub type Value = Located<ValueType>;
 
+/// big integers which fit into i64 are always turned back into Int
+/// so that there is only one way to represent each number
+pub fn int_value(n: BigInt) -> ValueType {
+    match n.to_i64() {
+        Some(n) => ValueType::Int(n),
+        None => ValueType::BigInt(n),
+    }
+}
+
+pub fn to_bigint(val: &ValueType) -> Option<BigInt> {
+    match val {
+        ValueType::Int(n) => Some(BigInt::from(*n)),
+        ValueType::BigInt(n) => Some(n.clone()),
+        _ => None,
+    }
+}
+
+/// arithmetic on integers, which continues with big integers when the result does not fit into i64
+/// returns None if the values are not both integers
+fn int_arith(
+    left: &ValueType,
+    right: &ValueType,
+    small: fn(i64, i64) -> Option<i64>,
+    big: fn(&BigInt, &BigInt) -> BigInt,
+) -> Option<ValueType> {
+    if let (ValueType::Int(a), ValueType::Int(b)) = (left, right) {
+        if let Some(n) = small(*a, *b) {
+            return Some(ValueType::Int(n));
+        }
+    }
+    Some(int_value(big(&to_bigint(left)?, &to_bigint(right)?)))
+}
+
+/// converts the float to an integer after rounding it with the function
+fn float_to_int(n: f64, round: fn(f64) -> f64) -> Result<ValueType, String> {
+    match BigInt::from_f64(round(n)) {
+        Some(n) => Ok(int_value(n)),
+        None => Err(format!("Cannot convert {n} to an integer")),
+    }
+}
+
+/// integers are already rounded
+fn round_number(args: &[ValueType], round: fn(f64) -> f64) -> Result<ValueType, String> {
+    let [val] = args else {
+        return Err(format!("Function takes exactly 1 argument, got: {}", args.len()));
+    };
+    match val {
+        ValueType::Int(_) | ValueType::BigInt(_) => Ok(val.clone()),
+        ValueType::Float(n) => float_to_int(*n, round),
+        _ => Err(format!("Expected a number, got: {val}")),
+    }
+}
+
+// argument checks shared by the native functions
+
+pub fn expect_bool(val: ValueType) -> Result<bool, String> {
+    match val {
+        ValueType::Bool(b) => Ok(b),
+        _ => Err(format!("Expected a bool, got: {val}")),
+    }
+}
+
+/// only small integers, e.g. for indexes and counts
+pub fn expect_int(val: &ValueType) -> Result<i64, String> {
+    match val {
+        ValueType::Int(n) => Ok(*n),
+        _ => Err(format!("Expected an integer, got: {val}")),
+    }
+}
+
+/// integers of any size
+pub fn expect_integer(val: &ValueType) -> Result<BigInt, String> {
+    to_bigint(val).ok_or_else(|| format!("Expected an integer, got: {val}"))
+}
+
+/// integers are converted to floats
+pub fn expect_number(val: &ValueType) -> Result<f64, String> {
+    match val {
+        ValueType::Int(n) => Ok(*n as f64),
+        ValueType::BigInt(n) => Ok(n.to_f64()),
+        ValueType::Float(n) => Ok(*n),
+        _ => Err(format!("Expected a number, got: {val}")),
+    }
+}
+
+pub fn expect_string(val: &ValueType) -> Result<&str, String> {
+    match val {
+        ValueType::String(s) => Ok(s),
+        _ => Err(format!("Expected a string, got: {val}")),
+    }
+}
+
+pub fn expect_list(val: &ValueType) -> Result<MList, String> {
+    match val {
+        ValueType::List(ls) => Ok(ls.clone()),
+        _ => Err(format!("Expected a list, got: {val}")),
+    }
+}
+
+/// a list of strings made by a native function at the location
+pub fn string_list(items: impl Iterator<Item = String>, loc: Location) -> ValueType {
+    ValueType::List(
+        items
+            .map(|s| Value {
+                val: ValueType::String(s),
+                loc,
+            })
+            .collect::<Vec<_>>()
+            .into(),
+    )
+}
+
+fn check_int_division(left: &ValueType, right: &ValueType) -> Result<(), String> {
+    match (left, right) {
+        (ValueType::Int(_) | ValueType::BigInt(_), ValueType::Int(0)) => Err("Attempted division by zero".to_string()),
+        _ => Ok(()),
+    }
+}
+
 // TODO: this is very smart, as it can currently hold only Functions
 // TODO: fix the precedence mess
 // and do this by making a "prelude" module which defines all the functions and whatnot,
@@ -75,19 +347,21 @@ pub type Value = Located<ValueType>;
 // PIE anyone?
 //
 // TODO: also move the vars in error messages into the string some time
-pub const NATIVE_OPERATORS: [(&str, Precedence, NativeFunction); 13] = [
+pub const NATIVE_OPERATORS: [(&str, Precedence, NativeFunction); 14] = [
     (
         "+",
         Precedence {
             prec: 5,
             assoc: Associativity::Left,
         },
-        |args| {
+        |_, args| {
             let [left, right] = &*args else {
                 return Err(format!("Wrong number of arguments: {}", args.len()));
             };
+            if let Some(res) = int_arith(left, right, i64::checked_add, |a, b| a + b) {
+                return Ok(res);
+            }
             Ok(match (left, right) {
-                (ValueType::Int(a), ValueType::Int(b)) => ValueType::Int(a + b),
                 (ValueType::Float(a), ValueType::Float(b)) => ValueType::Float(a + b),
                 (ValueType::String(a), ValueType::String(b)) => ValueType::String(a.clone() + b),
                 (ValueType::List(a), ValueType::List(b)) => {
@@ -110,12 +384,14 @@ pub const NATIVE_OPERATORS: [(&str, Precedence, NativeFunction); 13] = [
             prec: 5,
             assoc: Associativity::Left,
         },
-        |args| {
+        |_, args| {
             let [left, right] = &args[..] else {
                 return Err(format!("Wrong number of arguments: {}", args.len()));
             };
+            if let Some(res) = int_arith(left, right, i64::checked_sub, |a, b| a - b) {
+                return Ok(res);
+            }
             Ok(match (left, right) {
-                (ValueType::Int(a), ValueType::Int(b)) => ValueType::Int(a - b),
                 (ValueType::Float(a), ValueType::Float(b)) => ValueType::Float(a - b),
                 _ => return Err(format!("Invalid values: \"{left}\" and \"{right}\"")),
             })
@@ -127,12 +403,14 @@ pub const NATIVE_OPERATORS: [(&str, Precedence, NativeFunction); 13] = [
             prec: 6,
             assoc: Associativity::Left,
         },
-        |args| {
+        |_, args| {
             let [left, right] = &args[..] else {
                 return Err(format!("Wrong number of arguments: {}", args.len()));
             };
+            if let Some(res) = int_arith(left, right, i64::checked_mul, |a, b| a * b) {
+                return Ok(res);
+            }
             Ok(match (left, right) {
-                (ValueType::Int(a), ValueType::Int(b)) => ValueType::Int(a * b),
                 (ValueType::Float(a), ValueType::Float(b)) => ValueType::Float(a * b),
                 _ => return Err(format!("Invalid values: \"{left}\" and \"{right}\"")),
             })
@@ -144,17 +422,16 @@ pub const NATIVE_OPERATORS: [(&str, Precedence, NativeFunction); 13] = [
             prec: 6,
             assoc: Associativity::Left,
         },
-        |args| {
+        |_, args| {
             let [left, right] = &args[..] else {
                 return Err(format!("Wrong number of arguments: {}", args.len()));
             };
+            check_int_division(left, right)?;
+            // the minimum divided by -1 does not fit
+            if let Some(res) = int_arith(left, right, i64::checked_div, |a, b| a.div_rem(b).0) {
+                return Ok(res);
+            }
             Ok(match (left, right) {
-                (ValueType::Int(a), ValueType::Int(b)) => {
-                    if *b == 0 {
-                        return Err("Attempted division by zero".to_string());
-                    }
-                    ValueType::Int(a / b)
-                }
                 (ValueType::Float(a), ValueType::Float(b)) => ValueType::Float(a / b),
                 _ => return Err(format!("Invalid values: \"{left}\" and \"{right}\"")),
             })
@@ -166,12 +443,16 @@ pub const NATIVE_OPERATORS: [(&str, Precedence, NativeFunction); 13] = [
             prec: 6,
             assoc: Associativity::Left,
         },
-        |args| {
+        |_, args| {
             let [left, right] = &args[..] else {
                 return Err(format!("Wrong number of arguments: {}", args.len()));
             };
+            check_int_division(left, right)?;
+            // the minimum modulo -1 overflows in the division, but the remainder is just 0
+            if let Some(res) = int_arith(left, right, |a, b| Some(a.wrapping_rem(b)), |a, b| a.div_rem(b).1) {
+                return Ok(res);
+            }
             Ok(match (left, right) {
-                (ValueType::Int(a), ValueType::Int(b)) => ValueType::Int(a % b),
                 (ValueType::Float(a), ValueType::Float(b)) => ValueType::Float(a % b),
                 _ => return Err(format!("Invalid values: \"{left}\" and \"{right}\"")),
             })
@@ -183,17 +464,11 @@ pub const NATIVE_OPERATORS: [(&str, Precedence, NativeFunction); 13] = [
             prec: 4,
             assoc: Associativity::Left,
         },
-        |args| {
+        |_, args| {
             let [left, right] = &args[..] else {
                 return Err(format!("Wrong number of arguments: {}", args.len()));
             };
-            Ok(match (left, right) {
-                (ValueType::Int(a), ValueType::Int(b)) => ValueType::Bool(a == b),
-                (ValueType::Float(a), ValueType::Float(b)) => ValueType::Bool(a == b),
-                (ValueType::String(a), ValueType::String(b)) => ValueType::Bool(a == b),
-                (ValueType::Bool(a), ValueType::Bool(b)) => ValueType::Bool(a == b),
-                _ => return Err(format!("Invalid values: \"{left}\" and \"{right}\"")),
-            })
+            Ok(ValueType::Bool(left.equals(right)))
         },
     ),
     (
@@ -202,17 +477,24 @@ pub const NATIVE_OPERATORS: [(&str, Precedence, NativeFunction); 13] = [
             prec: 4,
             assoc: Associativity::Left,
         },
-        |args| {
+        |_, args| {
             let [left, right] = &args[..] else {
                 return Err(format!("Wrong number of arguments: {}", args.len()));
             };
-            Ok(match (left, right) {
-                (ValueType::Int(a), ValueType::Int(b)) => ValueType::Bool(a != b),
-                (ValueType::Float(a), ValueType::Float(b)) => ValueType::Bool(a != b),
-                (ValueType::String(a), ValueType::String(b)) => ValueType::Bool(a != b),
-                (ValueType::Bool(a), ValueType::Bool(b)) => ValueType::Bool(a != b),
-                _ => return Err(format!("Invalid values: \"{left}\" and \"{right}\"")),
-            })
+            Ok(ValueType::Bool(!left.equals(right)))
+        },
+    ),
+    (
+        "is",
+        Precedence {
+            prec: 4,
+            assoc: Associativity::Left,
+        },
+        |_, args| {
+            let [left, right] = &args[..] else {
+                return Err(format!("Wrong number of arguments: {}", args.len()));
+            };
+            Ok(ValueType::Bool(left.is(right)))
         },
     ),
     (
@@ -221,16 +503,14 @@ pub const NATIVE_OPERATORS: [(&str, Precedence, NativeFunction); 13] = [
             prec: 4,
             assoc: Associativity::Left,
         },
-        |args| {
+        |_, args| {
             let [left, right] = &args[..] else {
                 return Err(format!("Wrong number of arguments: {}", args.len()));
             };
-            Ok(match (left, right) {
-                (ValueType::Int(a), ValueType::Int(b)) => ValueType::Bool(a >= b),
-                (ValueType::Float(a), ValueType::Float(b)) => ValueType::Bool(a >= b),
-                (ValueType::Bool(a), ValueType::Bool(b)) => ValueType::Bool(a >= b),
-                _ => return Err(format!("Invalid values: \"{left}\" and \"{right}\"")),
-            })
+            Ok(ValueType::Bool(matches!(
+                left.compare(right)?,
+                Some(Ordering::Greater | Ordering::Equal)
+            )))
         },
     ),
     (
@@ -239,16 +519,14 @@ pub const NATIVE_OPERATORS: [(&str, Precedence, NativeFunction); 13] = [
             prec: 4,
             assoc: Associativity::Left,
         },
-        |args| {
+        |_, args| {
             let [left, right] = &args[..] else {
                 return Err(format!("Wrong number of arguments: {}", args.len()));
             };
-            Ok(match (left, right) {
-                (ValueType::Int(a), ValueType::Int(b)) => ValueType::Bool(a <= b),
-                (ValueType::Float(a), ValueType::Float(b)) => ValueType::Bool(a <= b),
-                (ValueType::Bool(a), ValueType::Bool(b)) => ValueType::Bool(a <= b),
-                _ => return Err(format!("Invalid values: \"{left}\" and \"{right}\"")),
-            })
+            Ok(ValueType::Bool(matches!(
+                left.compare(right)?,
+                Some(Ordering::Less | Ordering::Equal)
+            )))
         },
     ),
     (
@@ -257,16 +535,11 @@ pub const NATIVE_OPERATORS: [(&str, Precedence, NativeFunction); 13] = [
             prec: 4,
             assoc: Associativity::Left,
         },
-        |args| {
+        |_, args| {
             let [left, right] = &args[..] else {
                 return Err(format!("Wrong number of arguments: {}", args.len()));
             };
-            Ok(match (left, right) {
-                (ValueType::Int(a), ValueType::Int(b)) => ValueType::Bool(a > b),
-                (ValueType::Float(a), ValueType::Float(b)) => ValueType::Bool(a > b),
-                (ValueType::Bool(a), ValueType::Bool(b)) => ValueType::Bool(a > b),
-                _ => return Err(format!("Invalid values: \"{left}\" and \"{right}\"")),
-            })
+            Ok(ValueType::Bool(left.compare(right)? == Some(Ordering::Greater)))
         },
     ),
     (
@@ -275,16 +548,11 @@ pub const NATIVE_OPERATORS: [(&str, Precedence, NativeFunction); 13] = [
             prec: 4,
             assoc: Associativity::Left,
         },
-        |args| {
+        |_, args| {
             let [left, right] = &args[..] else {
                 return Err(format!("Wrong number of arguments: {}", args.len()));
             };
-            Ok(match (left, right) {
-                (ValueType::Int(a), ValueType::Int(b)) => ValueType::Bool(a < b),
-                (ValueType::Float(a), ValueType::Float(b)) => ValueType::Bool(a < b),
-                (ValueType::Bool(a), ValueType::Bool(b)) => ValueType::Bool(a < b),
-                _ => return Err(format!("Invalid values: \"{left}\" and \"{right}\"")),
-            })
+            Ok(ValueType::Bool(left.compare(right)? == Some(Ordering::Less)))
         },
     ),
     (
@@ -293,7 +561,7 @@ pub const NATIVE_OPERATORS: [(&str, Precedence, NativeFunction); 13] = [
             prec: 4,
             assoc: Associativity::Left,
         },
-        |args| {
+        |_, args| {
             let [left, right] = &args[..] else {
                 return Err(format!("Wrong number of arguments: {}", args.len()));
             };
@@ -309,7 +577,7 @@ pub const NATIVE_OPERATORS: [(&str, Precedence, NativeFunction); 13] = [
             prec: 4,
             assoc: Associativity::Left,
         },
-        |args| {
+        |_, args| {
             let [left, right] = &args[..] else {
                 return Err(format!("Wrong number of arguments: {}", args.len()));
             };
@@ -321,15 +589,43 @@ pub const NATIVE_OPERATORS: [(&str, Precedence, NativeFunction); 13] = [
     ),
 ];
 
-pub const NATIVE_FUNCS: [(&str, NativeFunction); 5] = [
-    ("print", |args| {
+/// operators which are not functions, as the interpreter evaluates them itself (see Expr::Coalesce)
+pub const SYNTAX_OPERATORS: [(&str, Precedence); 1] = [(
+    "??",
+    Precedence {
+        prec: 3,
+        assoc: Associativity::Right,
+    },
+)];
+
+/// the precedences of all builtin operators, used when reassociating
+pub fn operator_precedences() -> HashMap<String, Precedence> {
+    NATIVE_OPERATORS
+        .iter()
+        .map(|(name, prec, _)| (name.to_string(), *prec))
+        .chain(SYNTAX_OPERATORS.iter().map(|(name, prec)| (name.to_string(), *prec)))
+        .collect()
+}
+
+pub const NATIVE_VALUES: [(&str, ValueType); 1] = [("none", ValueType::Option(None))];
+
+pub const NATIVE_FUNCS: [(&str, NativeFunction); 14] = [
+    // strings are printed as they are, so that the output can be used by other programs
+    ("print", |_, args| {
         println!(
             "{}",
-            args.iter().map(|a| { a.to_string() }).collect::<Vec<_>>().join(" ")
+            args.iter()
+                .map(|a| match a {
+                    ValueType::String(s) => s.clone(),
+                    _ => a.to_string(),
+                })
+                .collect::<Vec<_>>()
+                .join(" ")
         );
         Ok(ValueType::Unit)
     }),
-    ("time", |args| {
+    // whole seconds, kept for compatibility, see the time module for the rest
+    ("time", |_, args| {
         if !args.is_empty() {
             return Err(format!("\"times\" function takes no arguments, got: {}", args.len()));
         }
@@ -342,19 +638,74 @@ pub const NATIVE_FUNCS: [(&str, NativeFunction); 5] = [
                 .unwrap(),
         ))
     }),
-    // NOTE: btw clippy complains about usize being cast to i32, just so you know
-    ("len", |args| {
+    // NOTE: btw clippy complains about usize being cast to i64, just so you know
+    ("len", |_, args| {
         if args.len() != 1 {
             return Err(format!("Function takes exactly 1 argument, got: {}", args.len()));
         }
         let val = &args.first().unwrap();
         Ok(ValueType::Int(match val {
-            ValueType::String(s) => s.len() as i32,
-            ValueType::List(ls) => ls.read(Vec::len) as i32,
+            ValueType::String(s) => s.chars().count() as i64,
+            ValueType::List(ls) => ls.read(Vec::len) as i64,
             _ => return Err(format!("Invalid value: {val}")),
         }))
     }),
-    ("$$not", |args| {
+    ("panic", |_, args| {
+        if args.len() != 1 {
+            return Err(format!("Function takes exactly 1 argument, got: {}", args.len()));
+        }
+        // strings would be shown with quotes otherwise
+        Err(match &args[0] {
+            ValueType::String(s) => format!("Panicked: {s}"),
+            val => format!("Panicked: {val}"),
+        })
+    }),
+    ("some", |_, args| {
+        if args.len() != 1 {
+            return Err(format!("Function takes exactly 1 argument, got: {}", args.len()));
+        }
+        Ok(ValueType::Option(Some(args[0].clone().into())))
+    }),
+    ("unwrap", |_, args| {
+        if args.len() != 1 {
+            return Err(format!("Function takes exactly 1 argument, got: {}", args.len()));
+        }
+        match &args[0] {
+            ValueType::Option(Some(val)) => Ok(*val.clone()),
+            ValueType::Option(None) => Err("Attempted to unwrap none".to_string()),
+            val => Err(format!("Expected an option, got: {val}")),
+        }
+    }),
+    ("is_some", |_, args| {
+        if args.len() != 1 {
+            return Err(format!("Function takes exactly 1 argument, got: {}", args.len()));
+        }
+        match &args[0] {
+            ValueType::Option(val) => Ok(ValueType::Bool(val.is_some())),
+            val => Err(format!("Expected an option, got: {val}")),
+        }
+    }),
+    // rounds towards zero
+    ("int", |_, args| round_number(&args, f64::trunc)),
+    // rounds half away from zero
+    ("round", |_, args| round_number(&args, f64::round)),
+    ("floor", |_, args| round_number(&args, f64::floor)),
+    ("ceil", |_, args| round_number(&args, f64::ceil)),
+    ("float", |_, args| {
+        let [val] = &args[..] else {
+            return Err(format!("Function takes exactly 1 argument, got: {}", args.len()));
+        };
+        match val {
+            ValueType::Int(n) => Ok(ValueType::Float(*n as f64)),
+            ValueType::BigInt(n) => match n.to_f64() {
+                f if f.is_infinite() => Err(format!("Integer is too big to be a float: {n}")),
+                f => Ok(ValueType::Float(f)),
+            },
+            ValueType::Float(_) => Ok(val.clone()),
+            _ => Err(format!("Expected a number, got: {val}")),
+        }
+    }),
+    ("$$not", |_, args| {
         if args.len() != 1 {
             return Err(format!("Function takes exactly 1 argument, got: {}", args.len()));
         }
@@ -364,13 +715,14 @@ pub const NATIVE_FUNCS: [(&str, NativeFunction); 5] = [
             _ => return Err("Expected a bool".to_string()),
         }))
     }),
-    ("$$neg", |args| {
+    ("$$neg", |_, args| {
         if args.len() != 1 {
             return Err(format!("Function takes exactly 1 argument, got: {}", args.len()));
         }
         let val = &args.first().unwrap();
         Ok(match val {
-            ValueType::Int(n) => ValueType::Int(-n),
+            ValueType::Int(n) => n.checked_neg().map_or_else(|| ValueType::BigInt(-&BigInt::from(*n)), ValueType::Int),
+            ValueType::BigInt(n) => int_value(-n),
             ValueType::Float(n) => ValueType::Float(-n),
             _ => return Err("Expected a number".to_string()),
         })
@@ -382,7 +734,29 @@ pub fn get_builtins() -> HashMap<String, ValueType> {
     let fns = NATIVE_FUNCS
         .map(|(name, f)| (name.to_string(), ValueType::NativeFunction(f)))
         .to_vec();
+    let lists = LIST_FUNCS.map(|(name, f)| (name.to_string(), ValueType::NativeFunction(f)));
+    let strings = STRING_FUNCS.map(|(name, f)| (name.to_string(), ValueType::NativeFunction(f)));
+    let math = MATH_FUNCS.map(|(name, f)| (name.to_string(), ValueType::NativeFunction(f)));
+    let files = FILE_FUNCS.map(|(name, f)| (name.to_string(), ValueType::NativeFunction(f)));
+    let stdin = STDIN_FUNCS.map(|(name, f)| (name.to_string(), ValueType::NativeFunction(f)));
+    let system = SYSTEM_FUNCS.map(|(name, f)| (name.to_string(), ValueType::NativeFunction(f)));
+    let random = RANDOM_FUNCS.map(|(name, f)| (name.to_string(), ValueType::NativeFunction(f)));
+    let json = JSON_FUNCS.map(|(name, f)| (name.to_string(), ValueType::NativeFunction(f)));
+    let time = TIME_FUNCS.map(|(name, f)| (name.to_string(), ValueType::NativeFunction(f)));
+    let vals = NATIVE_VALUES.map(|(name, val)| (name.to_string(), val));
+    let math_vals = MATH_VALUES.map(|(name, val)| (name.to_string(), val));
     let mut builtins = ops.to_vec();
     builtins.extend(fns);
+    builtins.extend(lists);
+    builtins.extend(strings);
+    builtins.extend(math);
+    builtins.extend(files);
+    builtins.extend(stdin);
+    builtins.extend(system);
+    builtins.extend(random);
+    builtins.extend(json);
+    builtins.extend(time);
+    builtins.extend(vals);
+    builtins.extend(math_vals);
     builtins.into_iter().collect::<HashMap<_, _>>()
 }
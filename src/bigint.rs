@@ -0,0 +1,304 @@
+use std::{
+    cmp::Ordering,
+    fmt::Display,
+    ops::{Add, Mul, Neg, Sub},
+};
+
+/// an arbitrary-precision integer
+/// integers are moved into it when they do not fit into i64
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BigInt {
+    negative: bool,
+    // the magnitude in base 2^32, least significant first
+    // there are no leading zeros, so zero is empty
+    digits: Vec<u32>,
+}
+
+const BASE_BITS: u32 = 32;
+// the largest power of 10 that fits into a digit, used for conversions from and to strings
+const DECIMAL_BASE: u32 = 1_000_000_000;
+const DECIMAL_DIGITS: usize = 9;
+
+impl BigInt {
+    fn new(negative: bool, mut digits: Vec<u32>) -> Self {
+        while digits.last() == Some(&0) {
+            digits.pop();
+        }
+        // there is no negative zero
+        let negative = negative && !digits.is_empty();
+        Self { negative, digits }
+    }
+
+    /// parses a string of decimal digits, returns None if it contains anything else
+    pub fn from_decimal(s: &str) -> Option<Self> {
+        if s.is_empty() || !s.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        let mut digits = vec![];
+        // the first chunk is shorter, so that the rest are whole
+        let first = s.len() % DECIMAL_DIGITS;
+        let chunks = (first > 0)
+            .then(|| &s[..first])
+            .into_iter()
+            .chain(s.as_bytes()[first..].chunks(DECIMAL_DIGITS).map(|c| std::str::from_utf8(c).unwrap()));
+        for chunk in chunks {
+            let n = chunk.parse::<u32>().unwrap();
+            mul_small_add(&mut digits, 10u32.pow(chunk.len() as u32), n);
+        }
+        Some(Self::new(false, digits))
+    }
+
+    /// returns None if it does not fit
+    pub fn to_i64(&self) -> Option<i64> {
+        if self.digits.len() > 2 {
+            return None;
+        }
+        let magnitude = self
+            .digits
+            .iter()
+            .rev()
+            .fold(0i128, |acc, d| (acc << BASE_BITS) | *d as i128);
+        i64::try_from(if self.negative { -magnitude } else { magnitude }).ok()
+    }
+
+    /// the float rounded towards zero, returns None for NaN and infinity
+    pub fn from_f64(n: f64) -> Option<Self> {
+        if !n.is_finite() {
+            return None;
+        }
+        let n = n.trunc();
+        if n.abs() < i64::MAX as f64 {
+            return Some(Self::from(n as i64));
+        }
+        // too big to have a fractional part, so it is the mantissa shifted by the exponent
+        let bits = n.to_bits();
+        let exponent = ((bits >> 52) & 0x7ff) as i64 - 1075;
+        let mantissa = (bits & ((1 << 52) - 1)) | (1 << 52);
+        let mut digits = vec![mantissa as u32, (mantissa >> BASE_BITS) as u32];
+        for _ in 0..exponent {
+            mul_small_add(&mut digits, 2, 0);
+        }
+        Some(Self::new(n < 0.0, digits))
+    }
+
+    /// the closest float, which is infinite if it is too big
+    pub fn to_f64(&self) -> f64 {
+        let magnitude = self
+            .digits
+            .iter()
+            .rev()
+            .fold(0.0, |acc, d| acc * (1u64 << BASE_BITS) as f64 + *d as f64);
+        if self.negative { -magnitude } else { magnitude }
+    }
+
+    pub fn abs(&self) -> BigInt {
+        Self::new(false, self.digits.clone())
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.digits.is_empty()
+    }
+
+    /// the number of bits of the magnitude, 0 for zero
+    pub fn bits(&self) -> u64 {
+        match self.digits.last() {
+            Some(top) => (self.digits.len() as u64 - 1) * BASE_BITS as u64 + (BASE_BITS - top.leading_zeros()) as u64,
+            None => 0,
+        }
+    }
+
+    /// truncating division and the remainder, the same as for i64
+    /// the divisor must not be zero
+    pub fn div_rem(&self, other: &BigInt) -> (BigInt, BigInt) {
+        assert!(!other.is_zero(), "Division by zero should be checked by the caller");
+        let (quot, rem) = div_rem_magnitude(&self.digits, &other.digits);
+        (
+            Self::new(self.negative != other.negative, quot),
+            Self::new(self.negative, rem),
+        )
+    }
+}
+
+impl From<i64> for BigInt {
+    fn from(n: i64) -> Self {
+        let magnitude = n.unsigned_abs();
+        Self::new(n < 0, vec![magnitude as u32, (magnitude >> BASE_BITS) as u32])
+    }
+}
+
+impl Display for BigInt {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_zero() {
+            return write!(f, "0");
+        }
+        let mut chunks = vec![];
+        let mut digits = self.digits.clone();
+        while !digits.is_empty() {
+            let (quot, rem) = div_rem_small(&digits, DECIMAL_BASE);
+            chunks.push(rem);
+            digits = quot;
+        }
+        let mut s = if self.negative { "-".to_string() } else { String::new() };
+        s += &chunks.pop().unwrap().to_string();
+        for chunk in chunks.iter().rev() {
+            s += &format!("{chunk:0DECIMAL_DIGITS$}");
+        }
+        write!(f, "{s}")
+    }
+}
+
+impl Ord for BigInt {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => cmp_magnitude(&self.digits, &other.digits),
+            (true, true) => cmp_magnitude(&other.digits, &self.digits),
+        }
+    }
+}
+
+impl PartialOrd for BigInt {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Neg for &BigInt {
+    type Output = BigInt;
+
+    fn neg(self) -> BigInt {
+        BigInt::new(!self.negative, self.digits.clone())
+    }
+}
+
+impl Add for &BigInt {
+    type Output = BigInt;
+
+    fn add(self, other: &BigInt) -> BigInt {
+        if self.negative == other.negative {
+            return BigInt::new(self.negative, add_magnitude(&self.digits, &other.digits));
+        }
+        // the sign is that of the one further from zero
+        match cmp_magnitude(&self.digits, &other.digits) {
+            Ordering::Less => BigInt::new(other.negative, sub_magnitude(&other.digits, &self.digits)),
+            _ => BigInt::new(self.negative, sub_magnitude(&self.digits, &other.digits)),
+        }
+    }
+}
+
+impl Sub for &BigInt {
+    type Output = BigInt;
+
+    fn sub(self, other: &BigInt) -> BigInt {
+        self + &-other
+    }
+}
+
+impl Mul for &BigInt {
+    type Output = BigInt;
+
+    fn mul(self, other: &BigInt) -> BigInt {
+        let mut res = vec![0u32; self.digits.len() + other.digits.len()];
+        for (i, a) in self.digits.iter().enumerate() {
+            let mut carry = 0u64;
+            for (j, b) in other.digits.iter().enumerate() {
+                let cur = res[i + j] as u64 + *a as u64 * *b as u64 + carry;
+                res[i + j] = cur as u32;
+                carry = cur >> BASE_BITS;
+            }
+            res[i + other.digits.len()] = carry as u32;
+        }
+        BigInt::new(self.negative != other.negative, res)
+    }
+}
+
+fn cmp_magnitude(a: &[u32], b: &[u32]) -> Ordering {
+    a.len().cmp(&b.len()).then_with(|| a.iter().rev().cmp(b.iter().rev()))
+}
+
+fn add_magnitude(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut res = vec![];
+    let mut carry = 0u64;
+    for i in 0..a.len().max(b.len()) {
+        let cur = *a.get(i).unwrap_or(&0) as u64 + *b.get(i).unwrap_or(&0) as u64 + carry;
+        res.push(cur as u32);
+        carry = cur >> BASE_BITS;
+    }
+    res.push(carry as u32);
+    res
+}
+
+/// a must not be smaller than b
+fn sub_magnitude(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut res = vec![];
+    let mut borrow = 0i64;
+    for (i, digit) in a.iter().enumerate() {
+        let mut cur = *digit as i64 - *b.get(i).unwrap_or(&0) as i64 - borrow;
+        borrow = 0;
+        if cur < 0 {
+            cur += 1 << BASE_BITS;
+            borrow = 1;
+        }
+        res.push(cur as u32);
+    }
+    res
+}
+
+/// digits = digits * mul + add
+fn mul_small_add(digits: &mut Vec<u32>, mul: u32, add: u32) {
+    let mut carry = add as u64;
+    for d in digits.iter_mut() {
+        let cur = *d as u64 * mul as u64 + carry;
+        *d = cur as u32;
+        carry = cur >> BASE_BITS;
+    }
+    if carry > 0 {
+        digits.push(carry as u32);
+    }
+}
+
+fn div_rem_small(digits: &[u32], div: u32) -> (Vec<u32>, u32) {
+    let mut quot = vec![0; digits.len()];
+    let mut rem = 0u64;
+    for (i, d) in digits.iter().enumerate().rev() {
+        let cur = (rem << BASE_BITS) | *d as u64;
+        quot[i] = (cur / div as u64) as u32;
+        rem = cur % div as u64;
+    }
+    while quot.last() == Some(&0) {
+        quot.pop();
+    }
+    (quot, rem as u32)
+}
+
+/// long division one bit at a time, good enough for numbers in scripts
+fn div_rem_magnitude(a: &[u32], b: &[u32]) -> (Vec<u32>, Vec<u32>) {
+    if let [div] = b {
+        let (quot, rem) = div_rem_small(a, *div);
+        return (quot, vec![rem]);
+    }
+    let mut quot = vec![0u32; a.len()];
+    let mut rem: Vec<u32> = vec![];
+    for i in (0..a.len() * BASE_BITS as usize).rev() {
+        // rem = rem * 2 + the next bit
+        let bit = (a[i / BASE_BITS as usize] >> (i % BASE_BITS as usize)) & 1;
+        let mut carry = bit;
+        for d in rem.iter_mut() {
+            let next = *d >> (BASE_BITS - 1);
+            *d = (*d << 1) | carry;
+            carry = next;
+        }
+        if carry > 0 {
+            rem.push(carry);
+        }
+        if cmp_magnitude(&rem, b) != Ordering::Less {
+            rem = sub_magnitude(&rem, b);
+            while rem.last() == Some(&0) {
+                rem.pop();
+            }
+            quot[i / BASE_BITS as usize] |= 1 << (i % BASE_BITS as usize);
+        }
+    }
+    (quot, rem)
+}
@@ -1,6 +1,7 @@
 use std::fmt::Display;
 
 use crate::associativity::Precedence;
+use crate::backend::value::ValueType;
 use crate::frontend::token::TokenType;
 use crate::located::Location;
 
@@ -30,7 +31,7 @@ pub enum ErrorType {
     UnknownUnaryOperator,
     InvalidAssignmentTarget,
     InvalidPrecedence,
-    PrecedenceOutOfRange(i32),
+    PrecedenceOutOfRange(i64),
     IncorrectOperatorParameterCount(usize),
     InvalidFunctionName,
     InvalidOperatorname,
@@ -50,17 +51,34 @@ pub enum ErrorType {
     UndeclaredItem,
     DuplicateParameter(String),
     DuplicateField(String),
+    MissingField(String, String), // field, struct
     ImplWithoutStruct(String),
+    NotATrait(String),
+    NotOverloadable(String),
+    MissingTraitMethod(String, String), // trait, method
+    MethodNotInTrait(String, String), // method, trait
+    TraitMethodParameterCount(String, usize, usize), // method, got, expected
+    MethodNeedsInstance(String, String), // method, struct
+    YieldOutsideGenerator,
+    YieldInLoopExpression,
+    UnknownLabel(String),
+    BreakWithValue,
+    AssignToConstant(String),
+    TestNotAtTopLevel(String),
     // varcheck warns
     ItemNotUsed(String),
     DeadCode,
     IfNeverExecutes,
     IfAlwaysExecutes,
     LoopNeverExecutes,
+    LoopNeverEnds,
+    UnnecessarySafeAccess,
+    // simplify
+    NotConstant,
     // interpreter
     ExpectedListIndex,
     ExpectedIndex,
-    IndexOutOfRange(i32, usize), // tried, max
+    IndexOutOfRange(i64, usize), // tried, max
     ExpectedBool,
     ItemNotCalleable,
     ExpectedUnaryNumber,
@@ -74,7 +92,15 @@ pub enum ErrorType {
     FieldNotFound(String, String),
     ExpectedInstance,
     UnknownField(String),
+    ExpectedInstanceOf(String),
     ImplNameNotAStruct(String),
+    UncaughtThrow(ValueType),
+    AssertionFailed(Option<String>),
+    InvalidOperatorResult(String),
+    GeneratorRunning,
+    NotIterable(String),
+    InvalidIteratorResult(String),
+    Exit(i32), // not an error, the program asked to stop with the code
     // other
     OtherError(String),
 }
@@ -111,7 +137,7 @@ impl ErrorType {
             Self::ExpectedIdentifier => "Expected an identifier".to_string(),
             Self::ExpectedFieldName => "Expected a field name".to_string(),
             Self::ExpectedStructName => "Expected a struct name".to_string(),
-            Self::NonFunStmtInImpl => "Only function definitions are allowed".to_string(),
+            Self::NonFunStmtInImpl => "Only function and constant definitions are allowed".to_string(),
             // reassoc
             Self::OperatorNotFound(s) => format!("Operator not found: {s}"),
             Self::IncompatiblePrecedence(op1, prec1, op2, prec2) => format!("Incompatible operator precedence: \"{op1}\" ({prec1:?}) and \"{op2}\" ({prec2:?}) - both have precedence {}", prec1.prec),
@@ -120,13 +146,28 @@ impl ErrorType {
             Self::UndeclaredItem => "Item not declared".to_string(),
             Self::DuplicateParameter(s) => format!("Duplicate parameter: {s}"),
             Self::DuplicateField(f) => format!("Duplicate parameter: {f}"),
+            Self::MissingField(field, name) => format!("Missing field \"{field}\" of struct \"{name}\""),
             Self::ImplWithoutStruct(name) => format!("Impl \"{name}\" does not have a corresponding struct"),
+            Self::NotATrait(name) => format!("\"{name}\" is not a trait"),
+            Self::NotOverloadable(op) => format!("Only builtin operators can be overloaded, got: \"{op}\""),
+            Self::MissingTraitMethod(trait_name, method) => format!("Method \"{method}\" of trait \"{trait_name}\" is not implemented"),
+            Self::MethodNotInTrait(method, trait_name) => format!("Method \"{method}\" is not a member of trait \"{trait_name}\""),
+            Self::TraitMethodParameterCount(method, n, expected) => format!("Method \"{method}\" has {n} parameters, but the trait declares {expected}"),
+            Self::YieldInLoopExpression => "Cannot yield inside a loop used as a value".to_string(),
+            Self::UnknownLabel(label) => format!("Label \"'{label}\" does not belong to any loop around"),
+            Self::BreakWithValue => "Only \"loop\" can break with a value".to_string(),
+            Self::AssignToConstant(name) => format!("Cannot assign to constant \"{name}\""),
+            Self::TestNotAtTopLevel(name) => format!("Test \"{name}\" must be declared at the top level"),
             // varcheck warns
             Self::ItemNotUsed(s) => format!("Item \"{s}\" not used"),
             Self::DeadCode => "Unreachable code".to_string(),
             Self::IfNeverExecutes => "If branch never executes".to_string(),
             Self::IfAlwaysExecutes => "If branch always executes".to_string(),
             Self::LoopNeverExecutes => "Loop never executes".to_string(),
+            Self::LoopNeverEnds => "Loop never ends, nothing breaks out of it".to_string(),
+            Self::UnnecessarySafeAccess => "Safe access on a value that is never none".to_string(),
+            // simplify
+            Self::NotConstant => "Expression cannot be evaluated at compile time".to_string(),
             // interpreter
             Self::ExpectedListIndex => "Expected a list index expression".to_string(),
             Self::ExpectedIndex => "Expected an integer index".to_string(),
@@ -144,7 +185,20 @@ impl ErrorType {
             Self::FieldNotFound(field, struc) => format!("Field \"{field}\" not found in \"{struc}\""),
             Self::ExpectedInstance => "Expected struct instance".to_string(),
             Self::UnknownField(name) => format!("Field \"{name}\" does not exist"),
+            Self::ExpectedInstanceOf(name) => format!("Expected an instance of \"{name}\""),
             Self::ImplNameNotAStruct(name) => format!("Value bound to \"{}\" is not a struct", name),
+            Self::UncaughtThrow(val) => format!("Uncaught thrown value: {val}"),
+            Self::AssertionFailed(None) => "Assertion failed".to_string(),
+            Self::AssertionFailed(Some(msg)) => format!("Assertion failed: {msg}"),
+            Self::InvalidOperatorResult(op) => format!("Operator \"{op}\" must return a bool"),
+            Self::YieldOutsideGenerator => "Yield outside of a generator".to_string(),
+            Self::GeneratorRunning => "Generator is already running".to_string(),
+            Self::NotIterable(val) => format!("Value \"{val}\" is not iterable"),
+            Self::InvalidIteratorResult(val) => format!("Method \"next\" must return an option, got \"{val}\""),
+            Self::Exit(code) => format!("Exited with code {code}"),
+            Self::MethodNeedsInstance(method, name) => {
+                format!("Method \"{method}\" takes self and has to be called on an instance of \"{name}\"")
+            }
             // other
             Self::OtherError(msg) => msg.clone(),
         }
@@ -158,6 +212,8 @@ impl ErrorType {
             | Self::IfNeverExecutes
             | Self::IfAlwaysExecutes
             | Self::LoopNeverExecutes
+            | Self::LoopNeverEnds
+            | Self::UnnecessarySafeAccess
         )
     }
 }
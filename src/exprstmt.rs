@@ -1,12 +1,13 @@
-use crate::{associativity::Precedence, located::Located};
+use crate::{associativity::Precedence, bigint::BigInt, located::Located};
 
 use std::fmt::Display;
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum Expr {
     Unit,
-    Int(i32),
-    Float(f32),
+    Int(i64),
+    BigInt(BigInt),
+    Float(f64),
     String(String),
     Bool(bool),
     Identifier(String),
@@ -19,6 +20,10 @@ pub enum Expr {
     Lambda(Vec<Identifier>, Vec<LStmt>), // |params| { block }
     FieldAccess(Box<LExpr>, Identifier),
     MethodAccess(Box<LExpr>, Identifier, Vec<LExpr>), // expr.name(args)
+    SafeFieldAccess(Box<LExpr>, Identifier), // expr?.name
+    SafeMethodAccess(Box<LExpr>, Identifier, Vec<LExpr>), // expr?.name(args)
+    StructLiteral(Identifier, Vec<(Identifier, LExpr)>, Option<Box<LExpr>>), // Name { field: expr, ..base }
+    Loop(Vec<LStmt>, Option<Identifier>), // 'label: loop { block }
 }
 
 impl Display for Expr {
@@ -26,6 +31,7 @@ impl Display for Expr {
         let s = match self {
             Self::Unit => "()".to_string(),
             Self::Int(n) => n.to_string(),
+            Self::BigInt(n) => n.to_string(),
             Self::Float(n) => n.to_string(),
             Self::String(s) => format!("\"{s}\""),
             Self::Bool(b) => b.to_string(),
@@ -52,12 +58,35 @@ impl Display for Expr {
                 "{callee}.{name}({args})",
                 args = args.iter().map(|e| { format!("{e}") }).collect::<Vec<_>>().join(", ")
             ),
+            Self::SafeFieldAccess(expr, name) => format!("{expr}?.{name}"),
+            Self::SafeMethodAccess(callee, name, args) => format!(
+                "{callee}?.{name}({args})",
+                args = args.iter().map(|e| { format!("{e}") }).collect::<Vec<_>>().join(", ")
+            ),
+            Self::StructLiteral(name, fields, base) => format!(
+                "{name} {{ {fields}{base} }}",
+                fields = fields
+                    .iter()
+                    .map(|(f, e)| format!("{f}: {e}"))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                base = base.as_ref().map(|b| format!(", ..{b}")).unwrap_or("".to_string())
+            ),
+            Self::Loop(block, label) => format!(
+                "{label}loop {{{block}}}",
+                label = fmt_label(label),
+                block = block.iter().map(|s| s.to_string()).collect::<Vec<_>>().join("\n")
+            ),
         };
         write!(f, "{s}")
     }
 }
 
 pub type LExpr = Located<Expr>;
+
+fn fmt_label(label: &Option<Identifier>) -> String {
+    label.as_ref().map(|l| format!("'{l}: ")).unwrap_or("".to_string())
+}
 // these were made as a simplification of Token to remove some pointless destructuring
 // they were always followed by "else unreachable" anyways
 pub type Symbol = Located<String>; // marks operators
@@ -72,16 +101,25 @@ pub enum Stmt {
     AssignIndex(LExpr, LExpr, LExpr), // expr[expr] = expr
     Block(Vec<LStmt>),
     If(Vec<(LExpr, Vec<LStmt>)>, Option<Block>),
-    While(LExpr, Vec<LStmt>),
+    While(LExpr, Vec<LStmt>, Option<Identifier>), // cond, block, label
     // name, parameters, body
     FunDecl(Identifier, Vec<Identifier>, Vec<LStmt>),
+    GenFunDecl(Identifier, Vec<Identifier>, Vec<LStmt>), // fun* name(params) { block }
     OperatorDecl(Symbol, (Identifier, Identifier), Vec<LStmt>, Precedence),
     Return(LExpr),
-    Break,
-    Continue,
-    Struct(Identifier, Vec<Identifier>),
+    Break(Option<Identifier>, Option<LExpr>), // break 'label value;
+    Continue(Option<Identifier>),
+    Struct(Identifier, Vec<(Identifier, Option<LExpr>)>), // name, fields with their defaults
     AssignStruct(LExpr, Identifier, LExpr), // expr.name = expr
-    Impl(Identifier, Vec<LStmt>),
+    Impl(Identifier, Option<Identifier>, Vec<LStmt>), // struct, trait, methods
+    Throw(LExpr),
+    // name, required methods (name, params), default methods
+    Trait(Identifier, Vec<(Identifier, Vec<Identifier>)>, Vec<LStmt>),
+    Const(Identifier, LExpr),
+    Yield(LExpr),
+    For(Identifier, LExpr, Vec<LStmt>, Option<Identifier>), // for name in expr { block }, label
+    Assert(LExpr, Option<LExpr>), // assert cond, message;
+    Test(Identifier, Vec<LStmt>), // test "name" { block }
 }
 impl Display for Stmt {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -115,8 +153,9 @@ impl Display for Stmt {
                     el = els.clone().map(|b| b.iter().map(|s| s.to_string()).collect::<Vec<_>>().join("\n")).unwrap_or("".to_string())
                 )
             }
-            Self::While(cond, block) => format!(
-                "while {cond} {{{block}}}",
+            Self::While(cond, block, label) => format!(
+                "{label}while {cond} {{{block}}}",
+                label = fmt_label(label),
                 block = block.iter().map(|s| s.to_string()).collect::<Vec<_>>().join("\n")
             ),
             Self::FunDecl(ident, params, block) => format!(
@@ -124,6 +163,11 @@ impl Display for Stmt {
                 params = params.iter().map(|s| s.to_string()).collect::<Vec<_>>().join(", "),
                 block = block.iter().map(|s| s.to_string()).collect::<Vec<_>>().join("\n")
             ),
+            Self::GenFunDecl(ident, params, block) => format!(
+                "fun* {ident}({params}){block}",
+                params = params.iter().map(|s| s.to_string()).collect::<Vec<_>>().join(", "),
+                block = block.iter().map(|s| s.to_string()).collect::<Vec<_>>().join("\n")
+            ),
             Self::OperatorDecl(ident, params, block, _) => format!(
                 "fun {ident}({}, {}){block}",
                 params.0,
@@ -131,15 +175,58 @@ impl Display for Stmt {
                 block = block.iter().map(|s| s.to_string()).collect::<Vec<_>>().join("\n")
             ),
             Self::Return(expr) => format!("return {expr};"),
-            Self::Break => "break;".to_string(),
-            Self::Continue => "continue;".to_string(),
+            Self::Break(label, expr) => format!(
+                "break{label}{expr};",
+                label = label.as_ref().map(|l| format!(" '{l}")).unwrap_or("".to_string()),
+                expr = expr.as_ref().map(|e| format!(" {e}")).unwrap_or("".to_string())
+            ),
+            Self::Continue(label) => format!(
+                "continue{};",
+                label.as_ref().map(|l| format!(" '{l}")).unwrap_or("".to_string())
+            ),
             Self::Struct(name, fields) => format!(
                 "struct {name} {{ {} }}",
-                fields.iter().map(|s| s.to_string()).collect::<Vec<_>>().join(", ")
+                fields
+                    .iter()
+                    .map(|(f, default)| match default {
+                        Some(default) => format!("{f} = {default}"),
+                        None => f.to_string(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ")
             ),
             Self::AssignStruct(expr1, name, expr2) => format!("{expr1}.{} = {expr2}", name.val),
-            Self::Impl(name, block) => format!(
-                "impl {name} {{\n{block}\n}}",
+            Self::Impl(name, trait_name, block) => format!(
+                "impl {trait_name}{name} {{\n{block}\n}}",
+                trait_name = trait_name.as_ref().map(|t| format!("{t} for ")).unwrap_or("".to_string()),
+                block = block.iter().map(|s| s.to_string()).collect::<Vec<_>>().join("\n")
+            ),
+            Self::Throw(expr) => format!("throw {expr};"),
+            Self::Trait(name, required, defaults) => format!(
+                "trait {name} {{\n{required}\n{defaults}\n}}",
+                required = required
+                    .iter()
+                    .map(|(name, params)| format!(
+                        "fun {name}({});",
+                        params.iter().map(|s| s.to_string()).collect::<Vec<_>>().join(", ")
+                    ))
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+                defaults = defaults.iter().map(|s| s.to_string()).collect::<Vec<_>>().join("\n")
+            ),
+            Self::Const(ident, expr) => format!("const {ident} = {expr};"),
+            Self::Yield(expr) => format!("yield {expr};"),
+            Self::For(ident, expr, block, label) => format!(
+                "{label}for {ident} in {expr} {{{block}}}",
+                label = fmt_label(label),
+                block = block.iter().map(|s| s.to_string()).collect::<Vec<_>>().join("\n")
+            ),
+            Self::Assert(cond, msg) => format!(
+                "assert {cond}{};",
+                msg.as_ref().map(|m| format!(", {m}")).unwrap_or("".to_string())
+            ),
+            Self::Test(name, block) => format!(
+                "test \"{name}\" {{{block}}}",
                 block = block.iter().map(|s| s.to_string()).collect::<Vec<_>>().join("\n")
             ),
         };
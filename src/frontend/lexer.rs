@@ -1,12 +1,13 @@
 use super::token::{Token, TokenType};
 use crate::{
+    bigint::BigInt,
     error::{Error, ErrorType},
     located::{Located, Location},
 };
 
 const SYMBOLS: &str = "+-*/=<>!|.$&@#?~^:%";
 
-const KEYWORDS: [(&str, TokenType); 14] = [
+const KEYWORDS: [(&str, TokenType); 22] = [
     ("let", TokenType::Let),
     ("fun", TokenType::Fun),
     ("true", TokenType::True),
@@ -21,6 +22,14 @@ const KEYWORDS: [(&str, TokenType); 14] = [
     ("infixl", TokenType::Infixl),
     ("struct", TokenType::Struct),
     ("impl", TokenType::Impl),
+    ("throw", TokenType::Throw),
+    ("trait", TokenType::Trait),
+    ("for", TokenType::For),
+    ("const", TokenType::Const),
+    ("yield", TokenType::Yield),
+    ("in", TokenType::In),
+    ("loop", TokenType::Loop),
+    ("assert", TokenType::Assert),
 ];
 
 const SPECIAL_SYMBOLS: [(char, TokenType); 8] = [
@@ -120,6 +129,16 @@ impl Lexer {
                         continue;
                     }
                 },
+                '\'' => {
+                    self.advance();
+                    let label = self.lex_identifier();
+                    if label.is_empty() {
+                        let err = self.error_minus_one(ErrorType::UnknownCharacter('\''));
+                        self.errs.push(err);
+                        continue;
+                    }
+                    TokenType::Label(label)
+                }
                 num if num.is_ascii_digit() => {
                     // floats: should be anything that matches <number>.<number>
                     // no spaces, missing whole/decimal part
@@ -133,12 +152,17 @@ impl Lexer {
                 }
                 ident if ident.is_alphabetic() || ident == '_' => {
                     let ident = self.lex_identifier();
-                    KEYWORDS
-                        .iter()
-                        .find(|x| x.0 == ident)
-                        .unwrap_or(&("", TokenType::Identifier(ident)))
-                        .clone()
-                        .1
+                    // an operator, so it is treated as any other symbol
+                    if ident == "is" {
+                        TokenType::Symbol(ident)
+                    } else {
+                        KEYWORDS
+                            .iter()
+                            .find(|x| x.0 == ident)
+                            .unwrap_or(&("", TokenType::Identifier(ident)))
+                            .clone()
+                            .1
+                    }
                 }
                 s if SPECIAL_SYMBOLS.map(|x| x.0).contains(&s) => {
                     self.advance();
@@ -150,6 +174,7 @@ impl Lexer {
                         "=" => TokenType::Equals,
                         "?" => TokenType::QuestionMark,
                         "." => TokenType::Dot,
+                        "?." => TokenType::QuestionDot,
                         "|" => TokenType::Pipe,
                         _ if sym.ends_with("*/") && sym[..sym.len() - 2].chars().all(|s| s == '*') => {
                             let err = self.error_minus_one(ErrorType::CommentSymbol);
@@ -234,9 +259,13 @@ impl Lexer {
         }
         Ok(if is_float {
             // TODO: overflows behaving funny
-            TokenType::Float(num.parse::<f32>().map_err(|_| self.error_minus_one(ErrorType::IntegerOverflow))?)
+            TokenType::Float(num.parse::<f64>().map_err(|_| self.error_minus_one(ErrorType::IntegerOverflow))?)
         } else {
-            TokenType::Int(num.parse::<i32>().map_err(|_| self.error_minus_one(ErrorType::IntegerOverflow))?)
+            match num.parse::<i64>() {
+                Ok(n) => TokenType::Int(n),
+                // too big, the digits have already been checked
+                Err(_) => TokenType::BigInt(BigInt::from_decimal(&num).unwrap()),
+            }
         })
     }
 
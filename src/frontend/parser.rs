@@ -41,6 +41,8 @@ struct Parser {
     tokens: Vec<Token>,
     idx: usize,
     errs: Vec<Error>,
+    // struct literals are not allowed in conditions, `if x {}` would be ambiguous otherwise
+    in_condition: bool,
 }
 
 impl Parser {
@@ -53,6 +55,7 @@ impl Parser {
             tokens,
             idx: 0,
             errs: vec![],
+            in_condition: false,
         }
     }
 
@@ -127,11 +130,13 @@ impl Parser {
         }
         let start = self.get_current().loc.start;
         self.advance();
+        let in_condition = mem::replace(&mut self.in_condition, false);
 
         // no items in between
         if cmp(&self.get_current().val, &end_tok) {
             let end = self.get_current().loc.end;
             self.advance();
+            self.in_condition = in_condition;
             return Ok((vec![], Location { start, end }));
         }
 
@@ -157,6 +162,7 @@ impl Parser {
         }
         let end = cur.loc.end;
         self.advance(); // move past ending token
+        self.in_condition = in_condition;
         Ok((items, Location { start, end }))
     }
 
@@ -200,25 +206,45 @@ impl Parser {
         match tok.val {
             TokenType::Let => self.parse_var_decl(),
             TokenType::If => self.parse_if_else(),
-            TokenType::While => self.parse_while(),
+            TokenType::While | TokenType::For | TokenType::Loop | TokenType::Label(_) => self.parse_labeled(),
             TokenType::Fun => self.parse_fun(false),
             TokenType::Infixl | TokenType::Infixr => self.parse_operator(),
             TokenType::Struct => self.parse_struct(),
             TokenType::Impl => self.parse_impl(),
+            TokenType::Trait => self.parse_trait(),
+            TokenType::Const => self.parse_const(),
             TokenType::Continue => {
                 self.advance();
+                let label = self.parse_label_name();
                 check_variant!(self, Semicolon, "Expected a semicolon \";\"")?;
                 Ok(LStmt {
-                    val: Stmt::Continue,
-                    loc: tok.loc,
+                    loc: Location {
+                        start: tok.loc.start,
+                        end: label.as_ref().map_or(tok.loc.end, |l| l.loc.end),
+                    },
+                    val: Stmt::Continue(label),
                 })
             }
             TokenType::Break => {
                 self.advance();
+                let label = self.parse_label_name();
+                let val = if is_typ!(self, Semicolon) {
+                    None
+                } else {
+                    Some(self.parse_expression()?)
+                };
                 check_variant!(self, Semicolon, "Expected a semicolon \";\"")?;
+                let end = match (&label, &val) {
+                    (_, Some(val)) => val.loc.end,
+                    (Some(label), None) => label.loc.end,
+                    (None, None) => tok.loc.end,
+                };
                 Ok(LStmt {
-                    val: Stmt::Break,
-                    loc: tok.loc,
+                    loc: Location {
+                        start: tok.loc.start,
+                        end,
+                    },
+                    val: Stmt::Break(label, val),
                 })
             }
             TokenType::Return => {
@@ -241,6 +267,56 @@ impl Parser {
                     val: Stmt::Return(val),
                 })
             }
+            TokenType::Yield => {
+                self.advance();
+                let val = if is_typ!(self, Semicolon) {
+                    // same as return
+                    LExpr {
+                        val: Expr::Unit,
+                        loc: tok.loc,
+                    }
+                } else {
+                    self.parse_expression()?
+                };
+                check_variant!(self, Semicolon, "Expected a semicolon \";\"")?;
+                Ok(LStmt {
+                    loc: Location {
+                        start: tok.loc.start,
+                        end: val.loc.end,
+                    },
+                    val: Stmt::Yield(val),
+                })
+            }
+            TokenType::Throw => {
+                self.advance();
+                let val = self.parse_expression()?;
+                check_variant!(self, Semicolon, "Expected a semicolon \";\"")?;
+                Ok(LStmt {
+                    loc: Location {
+                        start: tok.loc.start,
+                        end: val.loc.end,
+                    },
+                    val: Stmt::Throw(val),
+                })
+            }
+            TokenType::Assert => {
+                self.advance();
+                let cond = self.parse_expression()?;
+                let msg = if is_typ!(self, Comma) {
+                    self.advance();
+                    Some(self.parse_expression()?)
+                } else {
+                    None
+                };
+                check_variant!(self, Semicolon, "Expected a semicolon \";\"")?;
+                Ok(LStmt {
+                    loc: Location {
+                        start: tok.loc.start,
+                        end: msg.as_ref().unwrap_or(&cond).loc.end,
+                    },
+                    val: Stmt::Assert(cond, msg),
+                })
+            }
             TokenType::LBrace => {
                 let bl = self.parse_block()?;
                 Ok(LStmt {
@@ -248,15 +324,39 @@ impl Parser {
                     loc: bl.loc,
                 })
             }
+            // "test" is not a keyword, so that it can still be used as a name
+            TokenType::Identifier(name)
+                if name == "test" && matches!(self.peek(1).map(|t| &t.val), Some(TokenType::String(_))) =>
+            {
+                self.parse_test()
+            }
             _ => self.parse_assignment(),
         }
     }
 
+    fn parse_test(&mut self) -> Result<LStmt, Error> {
+        let start = self.get_current().loc.start;
+        self.advance();
+        let Token { val: TokenType::String(name), loc } = self.get_current().clone() else {
+            unreachable!("Checked in parse_statement")
+        };
+        self.advance();
+        let block = self.parse_block()?;
+        Ok(LStmt {
+            val: Stmt::Test(Identifier { val: name, loc }, block.val),
+            loc: Location {
+                start,
+                end: block.loc.end,
+            },
+        })
+    }
+
     fn parse_block(&mut self) -> Result<Located<Vec<LStmt>>, Error> {
         // maybe can be changed into get + advance?
         let start = check_variant!(self, LBrace, "Expected { at the beginning of the block")?
             .loc
             .start;
+        let in_condition = mem::replace(&mut self.in_condition, false);
 
         let mut ls = vec![];
         while !self.is_at_end()
@@ -275,6 +375,7 @@ impl Parser {
         let end = check_variant!(self, RBrace, "Expected } at the end of the block")?
             .loc
             .end;
+        self.in_condition = in_condition;
 
         Ok(Located {
             val: ls,
@@ -306,7 +407,7 @@ impl Parser {
 
         let mut blocks = vec![];
 
-        let cond = self.parse_expression()?;
+        let cond = self.parse_condition()?;
         let if_block = self.parse_block()?;
 
         blocks.push((cond, if_block.val));
@@ -318,7 +419,7 @@ impl Parser {
 
             let cond = if is_typ!(self, If) {
                 self.advance();
-                self.parse_expression()?
+                self.parse_condition()?
             } else {
                 let bl = self.parse_block()?;
                 end = bl.loc.end;
@@ -337,14 +438,99 @@ impl Parser {
         })
     }
 
-    fn parse_while(&mut self) -> Result<LStmt, Error> {
+    /// the label of a loop, i.e. "'name:"
+    fn parse_label(&mut self) -> Result<Option<Identifier>, Error> {
+        let Some(label) = self.parse_label_name() else {
+            return Ok(None);
+        };
+        if !matches!(&self.get_current().val, TokenType::Symbol(s) if s == ":") {
+            return Err(Error {
+                msg: ErrorType::ExpectedToken("Expected a colon \":\"".to_string()),
+                lines: vec![self.get_current().loc],
+            });
+        }
+        self.advance();
+        Ok(Some(label))
+    }
+
+    /// the label used by break and continue, without the colon
+    fn parse_label_name(&mut self) -> Option<Identifier> {
+        let tok = self.get_current().clone();
+        let TokenType::Label(name) = tok.val else {
+            return None;
+        };
+        self.advance();
+        Some(Identifier { val: name, loc: tok.loc })
+    }
+
+    /// any loop, optionally labeled
+    /// "loop" is an expression, but it does not need a semicolon when used as a statement
+    fn parse_labeled(&mut self) -> Result<LStmt, Error> {
+        let start = self.get_current().loc.start;
+        let label = self.parse_label()?;
+        let stmt = match self.get_current().val {
+            TokenType::While => self.parse_while(label)?,
+            TokenType::For => self.parse_for(label)?,
+            TokenType::Loop => {
+                let expr = self.parse_loop(label, start)?;
+                return Ok(LStmt {
+                    loc: expr.loc,
+                    val: Stmt::Expr(expr),
+                });
+            }
+            _ => {
+                return Err(Error {
+                    msg: ErrorType::ExpectedToken("Expected a loop after the label".to_string()),
+                    lines: vec![self.get_current().loc],
+                })
+            }
+        };
+        Ok(LStmt {
+            loc: Location {
+                start,
+                end: stmt.loc.end,
+            },
+            val: stmt.val,
+        })
+    }
+
+    fn parse_loop(&mut self, label: Option<Identifier>, start: usize) -> Result<LExpr, Error> {
+        check_variant!(self, Loop, "Expected a loop after the label")?;
+        let block = self.parse_block()?;
+
+        Ok(LExpr {
+            val: Expr::Loop(block.val, label),
+            loc: Location {
+                start,
+                end: block.loc.end,
+            },
+        })
+    }
+
+    fn parse_while(&mut self, label: Option<Identifier>) -> Result<LStmt, Error> {
         let start = self.get_current().loc.start;
         self.advance(); // move past keyword
-        let cond = self.parse_expression()?;
+        let cond = self.parse_condition()?;
         let block = self.parse_block()?;
 
         Ok(LStmt {
-            val: Stmt::While(cond, block.val),
+            val: Stmt::While(cond, block.val, label),
+            loc: Location {
+                start,
+                end: block.loc.end,
+            },
+        })
+    }
+    fn parse_for(&mut self, label: Option<Identifier>) -> Result<LStmt, Error> {
+        let start = self.get_current().loc.start;
+        self.advance(); // move past keyword
+        let name = self.parse_ident()?;
+        check_variant!(self, In, "Expected \"in\"")?;
+        let iterable = self.parse_condition()?;
+        let block = self.parse_block()?;
+
+        Ok(LStmt {
+            val: Stmt::For(name, iterable, block.val, label),
             loc: Location {
                 start,
                 end: block.loc.end,
@@ -355,6 +541,19 @@ impl Parser {
         let start = self.get_current().loc.start;
         self.advance(); // move past keyword
 
+        // "fun* name" is a generator, while "fun *(a, b)" is an operator
+        let generator = matches!(&self.get_current().val, TokenType::Symbol(s) if s == "*")
+            && matches!(
+                self.peek(1),
+                Some(Token {
+                    val: TokenType::Identifier(_),
+                    ..
+                })
+            );
+        if generator {
+            self.advance();
+        }
+
         let tok = self.get_current().clone();
         let (op, name) = match tok.val {
             TokenType::Identifier(name) => {
@@ -379,7 +578,12 @@ impl Parser {
         let (params, _) = self.sep(TokenType::LParen, TokenType::RParen, Self::parse_ident)?;
         let block = self.parse_block()?;
         // TODO: horrible cheating, but eh
-        if !op {
+        if generator {
+            Ok(LStmt {
+                val: Stmt::GenFunDecl(Identifier { val: name, loc: tok.loc }, params, block.val),
+                loc: Location { start, end: block.loc.end },
+            })
+        } else if !op {
             Ok(LStmt {
                 val: Stmt::FunDecl(
                     Identifier { val: name, loc: tok.loc },
@@ -512,7 +716,7 @@ impl Parser {
         let name = self.parse_ident()?;
 
         // TODO: does not give ExpectedFieldName error
-        let fields = self.sep(TokenType::LBrace, TokenType::RBrace, Self::parse_ident)?;
+        let fields = self.sep(TokenType::LBrace, TokenType::RBrace, Self::parse_field)?;
 
         Ok(LStmt {
             val: Stmt::Struct(name, fields.0),
@@ -520,24 +724,112 @@ impl Parser {
         })
     }
 
+    /// field of a struct declaration with an optional default value
+    fn parse_field(&mut self) -> Result<(Identifier, Option<LExpr>), Error> {
+        let name = self.parse_ident()?;
+        if !is_typ!(self, Equals) {
+            return Ok((name, None));
+        }
+        self.advance();
+        Ok((name, Some(self.parse_expression()?)))
+    }
+
     fn parse_impl(&mut self) -> Result<LStmt, Error> {
         let start = self.get_current().loc.start;
         self.advance();
 
         let name = self.parse_ident()?;
+        // impl Trait for Struct
+        let (name, trait_name) = if is_typ!(self, For) {
+            self.advance();
+            (self.parse_ident()?, Some(name))
+        } else {
+            (name, None)
+        };
 
-        let block = self.parse_block()?;
-        for s in block.val.iter() {
-            if !matches!(s.val, Stmt::FunDecl(..)) {
+        check_variant!(self, LBrace, "Expected { at the beginning of the block")?;
+        let mut block = vec![];
+        while !is_typ!(self, RBrace) && !is_typ!(self, Eof) {
+            block.push(match self.get_current().val {
+                TokenType::Fun => self.parse_fun(false)?,
+                TokenType::Const => self.parse_const()?,
+                _ => {
+                    return Err(Error {
+                        msg: ErrorType::NonFunStmtInImpl,
+                        lines: vec![self.get_current().loc],
+                    })
+                }
+            });
+        }
+        let end = check_variant!(self, RBrace, "Expected } at the end of the block")?
+            .loc
+            .end;
+        Ok(LStmt {
+            val: Stmt::Impl(name, trait_name, block),
+            loc: Location { start, end },
+        })
+    }
+
+    fn parse_const(&mut self) -> Result<LStmt, Error> {
+        let start = self.get_current().loc.start;
+        self.advance();
+
+        let name = self.parse_ident()?;
+
+        check_variant!(self, Equals, "Expected an equals symbol")?;
+        let expr = self.parse_expression()?;
+        check_variant!(self, Semicolon, "Expected a semicolon \";\"")?;
+        Ok(LStmt {
+            loc: Location {
+                start,
+                end: expr.loc.end,
+            },
+            val: Stmt::Const(name, expr),
+        })
+    }
+
+    fn parse_trait(&mut self) -> Result<LStmt, Error> {
+        let start = self.get_current().loc.start;
+        self.advance();
+
+        let name = self.parse_ident()?;
+        check_variant!(self, LBrace, "Expected { at the beginning of the block")?;
+
+        let mut required = vec![];
+        let mut defaults = vec![];
+        while !is_typ!(self, RBrace) && !is_typ!(self, Eof) {
+            let fun_start = self.get_current().loc.start;
+            if !is_typ!(self, Fun) {
                 return Err(Error {
                     msg: ErrorType::NonFunStmtInImpl,
-                    lines: vec![s.loc],
+                    lines: vec![self.get_current().loc],
                 });
             }
+            self.advance();
+            let method = self.parse_ident()?;
+            let (params, _) = self.sep(TokenType::LParen, TokenType::RParen, Self::parse_ident)?;
+            // a method without a body has to be provided by the impl
+            if is_typ!(self, Semicolon) {
+                self.advance();
+                required.push((method, params));
+                continue;
+            }
+            let block = self.parse_block()?;
+            defaults.push(LStmt {
+                val: Stmt::FunDecl(method, params, block.val),
+                loc: Location {
+                    start: fun_start,
+                    end: block.loc.end,
+                },
+            });
         }
+        let end = check_variant!(self, RBrace, "Expected } at the end of the block")?
+            .loc
+            .end;
+
         Ok(LStmt {
-            val: Stmt::Impl(name, block.val),
-            loc: Location { start, end: block.loc.end },
+            val: Stmt::Trait(name, required, defaults),
+            loc: Location { start, end },
         })
     }
 
@@ -545,6 +837,15 @@ impl Parser {
         self.parse_binary()
     }
 
+    /// an expression followed by a block, so it cannot contain a struct literal
+    /// unless it is nested in parentheses, brackets or a block
+    fn parse_condition(&mut self) -> Result<LExpr, Error> {
+        let in_condition = mem::replace(&mut self.in_condition, true);
+        let expr = self.parse_expression();
+        self.in_condition = in_condition;
+        expr
+    }
+
     fn parse_binary(&mut self) -> Result<LExpr, Error> {
         let left = self.parse_unary()?;
         // if it is a symbol, look for nested binary operator
@@ -619,7 +920,9 @@ impl Parser {
                         val: Expr::Index(expr.into(), idx.into()),
                     };
                 }
-                TokenType::Dot => {
+                TokenType::Dot | TokenType::QuestionDot => {
+                    // safe access (?.) is the same, just short-circuits on none
+                    let safe = is_typ!(self, QuestionDot);
                     self.advance();
                     let name = self.parse_ident()?;
                     expr = if is_typ!(self, LParen) {
@@ -628,12 +931,20 @@ impl Parser {
                             self.sep(TokenType::LParen, TokenType::RParen, Self::parse_expression)?;
                         LExpr {
                             loc: Location { start, end: end_loc.end },
-                            val: Expr::MethodAccess(expr.into(), name, params),
+                            val: if safe {
+                                Expr::SafeMethodAccess(expr.into(), name, params)
+                            } else {
+                                Expr::MethodAccess(expr.into(), name, params)
+                            },
                         }
                     } else {
                         LExpr {
                             loc: Location { start, end: name.loc.end },
-                            val: Expr::FieldAccess(expr.into(), name),
+                            val: if safe {
+                                Expr::SafeFieldAccess(expr.into(), name)
+                            } else {
+                                Expr::FieldAccess(expr.into(), name)
+                            },
                         }
                     };
                 }
@@ -678,6 +989,55 @@ impl Parser {
         })
     }
 
+    /// Name { field: expr, ..base }
+    /// the base has to be the last one
+    fn parse_struct_literal(&mut self, name: Identifier) -> Result<LExpr, Error> {
+        self.advance(); // move past the brace
+        let in_condition = mem::replace(&mut self.in_condition, false);
+
+        let mut fields = vec![];
+        let mut base = None;
+        while !is_typ!(self, RBrace) {
+            if matches!(&self.get_current().val, TokenType::Symbol(s) if s == "..") {
+                self.advance();
+                base = Some(self.parse_expression()?.into());
+                break;
+            }
+            let field = match self.parse_ident() {
+                Ok(field) => field,
+                Err(err) => {
+                    return Err(Error {
+                        msg: ErrorType::ExpectedFieldName,
+                        lines: err.lines,
+                    })
+                }
+            };
+            if !matches!(&self.get_current().val, TokenType::Symbol(s) if s == ":") {
+                return Err(Error {
+                    msg: ErrorType::ExpectedToken("Expected a colon \":\"".to_string()),
+                    lines: vec![self.get_current().loc],
+                });
+            }
+            self.advance();
+            fields.push((field, self.parse_expression()?));
+            if !is_typ!(self, Comma) {
+                break;
+            }
+            self.advance();
+        }
+        let end = check_variant!(self, RBrace, "Expected } at the end of the struct")?
+            .loc
+            .end;
+        self.in_condition = in_condition;
+        Ok(LExpr {
+            loc: Location {
+                start: name.loc.start,
+                end,
+            },
+            val: Expr::StructLiteral(name, fields, base),
+        })
+    }
+
     fn parse_primary(&mut self) -> Result<LExpr, Error> {
         let tok = self.get_current().clone();
         let expr = match &tok.val {
@@ -689,12 +1049,22 @@ impl Parser {
                 self.advance();
                 Expr::Int(*n)
             }
+            TokenType::BigInt(n) => {
+                self.advance();
+                Expr::BigInt(n.clone())
+            }
             TokenType::Float(n) => {
                 self.advance();
                 Expr::Float(*n)
             }
             TokenType::Identifier(ident) => {
                 self.advance();
+                if is_typ!(self, LBrace) && !self.in_condition {
+                    return self.parse_struct_literal(Identifier {
+                        val: ident.clone(),
+                        loc: tok.loc,
+                    });
+                }
                 Expr::Identifier(ident.clone())
             }
             TokenType::True => {
@@ -722,7 +1092,12 @@ impl Parser {
                         self.advance();
                         Expr::Identifier(sym)
                     }
-                    _ => Expr::Parens(self.parse_expression()?.into()),
+                    _ => {
+                        let in_condition = mem::replace(&mut self.in_condition, false);
+                        let expr = self.parse_expression();
+                        self.in_condition = in_condition;
+                        Expr::Parens(expr?.into())
+                    }
                 };
                 let end = check_variant!(self, RParen, "Expected a closing parenthesis")?.loc.end;
                 return Ok(LExpr {
@@ -749,6 +1124,10 @@ impl Parser {
             TokenType::Pipe => {
                 return self.parse_lambda(true);
             }
+            TokenType::Loop | TokenType::Label(_) => {
+                let label = self.parse_label()?;
+                return self.parse_loop(label, tok.loc.start);
+            }
             _ => {
                 return Err(Error {
                     msg: ErrorType::UnknownElement(tok.val),
@@ -2,6 +2,7 @@ use std::collections::HashMap;
 
 use crate::{
     associativity::{Associativity, Precedence},
+    bigint::BigInt,
     error::Error,
     error::ErrorType,
     exprstmt::*,
@@ -153,14 +154,14 @@ impl StmtVisitor<LStmt> for Reassociate {
             loc,
         })
     }
-    fn whiles(&mut self, loc: Location, cond: LExpr, block: Vec<LStmt>) -> Result<LStmt, Error> {
+    fn whiles(&mut self, loc: Location, cond: LExpr, block: Vec<LStmt>, label: Option<Identifier>) -> Result<LStmt, Error> {
         let cond = self.visit_expr(cond)?;
         let mut block2 = vec![];
         for s in block {
             block2.push(self.visit_stmt(s)?);
         }
         Ok(LStmt {
-            val: Stmt::While(cond, block2),
+            val: Stmt::While(cond, block2, label),
             loc,
         })
     }
@@ -180,6 +181,22 @@ impl StmtVisitor<LStmt> for Reassociate {
             loc,
         })
     }
+    fn gen_fun(
+        &mut self,
+        loc: Location,
+        name: Identifier,
+        params: Vec<Identifier>,
+        block: Vec<LStmt>,
+    ) -> Result<LStmt, Error> {
+        let mut block2 = vec![];
+        for s in block {
+            block2.push(self.visit_stmt(s)?);
+        }
+        Ok(LStmt {
+            val: Stmt::GenFunDecl(name, params, block2),
+            loc,
+        })
+    }
     fn operator(
         &mut self,
         loc: Location,
@@ -205,21 +222,25 @@ impl StmtVisitor<LStmt> for Reassociate {
             loc,
         })
     }
-    fn cont(&mut self, loc: Location) -> Result<LStmt, Error> {
+    fn cont(&mut self, loc: Location, label: Option<Identifier>) -> Result<LStmt, Error> {
         Ok(LStmt {
-            val: Stmt::Continue,
+            val: Stmt::Continue(label),
             loc,
         })
     }
-    fn brek(&mut self, loc: Location) -> Result<LStmt, Error> {
+    fn brek(&mut self, loc: Location, label: Option<Identifier>, expr: Option<LExpr>) -> Result<LStmt, Error> {
         Ok(LStmt {
-            val: Stmt::Continue,
+            val: Stmt::Break(label, expr.map(|e| self.visit_expr(e)).transpose()?),
             loc,
         })
     }
-    fn struc(&mut self, loc: Location, name: Identifier, fields: Vec<Identifier>) -> Result<LStmt, Error> {
+    fn struc(&mut self, loc: Location, name: Identifier, fields: Vec<(Identifier, Option<LExpr>)>) -> Result<LStmt, Error> {
+        let mut fields2 = vec![];
+        for (f, default) in fields {
+            fields2.push((f, default.map(|d| self.visit_expr(d)).transpose()?));
+        }
         Ok(LStmt {
-            val: Stmt::Struct(name, fields),
+            val: Stmt::Struct(name, fields2),
             loc,
         })
     }
@@ -231,13 +252,95 @@ impl StmtVisitor<LStmt> for Reassociate {
             loc,
         })
     }
-    fn imp(&mut self, loc: Location, name: Identifier, block: Vec<LStmt>) -> Result<LStmt, Error> {
+    fn imp(&mut self, loc: Location, name: Identifier, trait_name: Option<Identifier>, block: Vec<LStmt>) -> Result<LStmt, Error> {
+        let mut block2 = vec![];
+        for s in block {
+            // overloading an operator must not change its precedence
+            let s2 = match s.val {
+                Stmt::OperatorDecl(op, params, body, prec) => {
+                    let mut body2 = vec![];
+                    for s in body {
+                        body2.push(self.visit_stmt(s)?);
+                    }
+                    LStmt {
+                        val: Stmt::OperatorDecl(op, params, body2, prec),
+                        loc: s.loc,
+                    }
+                }
+                _ => self.visit_stmt(s)?,
+            };
+            block2.push(s2);
+        }
+        Ok(LStmt {
+            val: Stmt::Impl(name, trait_name, block2),
+            loc,
+        })
+    }
+    fn trai(
+        &mut self,
+        loc: Location,
+        name: Identifier,
+        required: Vec<(Identifier, Vec<Identifier>)>,
+        defaults: Vec<LStmt>,
+    ) -> Result<LStmt, Error> {
+        let mut defaults2 = vec![];
+        for s in defaults {
+            defaults2.push(self.visit_stmt(s)?);
+        }
+        Ok(LStmt {
+            val: Stmt::Trait(name, required, defaults2),
+            loc,
+        })
+    }
+    fn cons(&mut self, loc: Location, ident: Identifier, expr: LExpr) -> Result<LStmt, Error> {
+        Ok(LStmt {
+            val: Stmt::Const(ident, self.visit_expr(expr)?),
+            loc,
+        })
+    }
+    fn yiel(&mut self, loc: Location, expr: LExpr) -> Result<LStmt, Error> {
+        Ok(LStmt {
+            val: Stmt::Yield(self.visit_expr(expr)?),
+            loc,
+        })
+    }
+    fn fors(
+        &mut self,
+        loc: Location,
+        ident: Identifier,
+        expr: LExpr,
+        block: Vec<LStmt>,
+        label: Option<Identifier>,
+    ) -> Result<LStmt, Error> {
+        let expr = self.visit_expr(expr)?;
+        let mut block2 = vec![];
+        for s in block {
+            block2.push(self.visit_stmt(s)?);
+        }
+        Ok(LStmt {
+            val: Stmt::For(ident, expr, block2, label),
+            loc,
+        })
+    }
+    fn throw(&mut self, loc: Location, expr: LExpr) -> Result<LStmt, Error> {
+        Ok(LStmt {
+            val: Stmt::Throw(self.visit_expr(expr)?),
+            loc,
+        })
+    }
+    fn asser(&mut self, loc: Location, cond: LExpr, msg: Option<LExpr>) -> Result<LStmt, Error> {
+        Ok(LStmt {
+            val: Stmt::Assert(self.visit_expr(cond)?, msg.map(|m| self.visit_expr(m)).transpose()?),
+            loc,
+        })
+    }
+    fn test(&mut self, loc: Location, name: Identifier, block: Vec<LStmt>) -> Result<LStmt, Error> {
         let mut block2 = vec![];
         for s in block {
             block2.push(self.visit_stmt(s)?);
         }
         Ok(LStmt {
-            val: Stmt::Impl(name, block2),
+            val: Stmt::Test(name, block2),
             loc,
         })
     }
@@ -250,13 +353,19 @@ impl ExprVisitor<LExpr> for Reassociate {
             loc,
         })
     }
-    fn int(&mut self, loc: Location, n: i32) -> Result<LExpr, Error> {
+    fn int(&mut self, loc: Location, n: i64) -> Result<LExpr, Error> {
         Ok(LExpr {
             val: Expr::Int(n),
             loc,
         })
     }
-    fn float(&mut self, loc: Location, n: f32) -> Result<LExpr, Error> {
+    fn bigint(&mut self, loc: Location, n: BigInt) -> Result<LExpr, Error> {
+        Ok(LExpr {
+            val: Expr::BigInt(n),
+            loc,
+        })
+    }
+    fn float(&mut self, loc: Location, n: f64) -> Result<LExpr, Error> {
         Ok(LExpr {
             val: Expr::Float(n),
             loc,
@@ -349,4 +458,49 @@ impl ExprVisitor<LExpr> for Reassociate {
             loc,
         })
     }
+    fn safe_field(&mut self, loc: Location, expr: LExpr, name: Identifier) -> Result<LExpr, Error> {
+        let expr2 = self.visit_expr(expr)?;
+        Ok(LExpr {
+            val: Expr::SafeFieldAccess(expr2.into(), name),
+            loc,
+        })
+    }
+    fn safe_method(&mut self, loc: Location, callee: LExpr, name: Identifier, args: Vec<LExpr>) -> Result<LExpr, Error> {
+        let callee2 = self.visit_expr(callee)?;
+        let mut args2 = vec![];
+        for e in args {
+            args2.push(self.visit_expr(e)?);
+        }
+        Ok(LExpr {
+            val: Expr::SafeMethodAccess(callee2.into(), name, args2),
+            loc,
+        })
+    }
+    fn struct_literal(
+        &mut self,
+        loc: Location,
+        name: Identifier,
+        fields: Vec<(Identifier, LExpr)>,
+        base: Option<LExpr>,
+    ) -> Result<LExpr, Error> {
+        let mut fields2 = vec![];
+        for (f, e) in fields {
+            fields2.push((f, self.visit_expr(e)?));
+        }
+        let base2 = base.map(|b| self.visit_expr(b)).transpose()?;
+        Ok(LExpr {
+            val: Expr::StructLiteral(name, fields2, base2.map(|b| b.into())),
+            loc,
+        })
+    }
+    fn loops(&mut self, loc: Location, block: Vec<LStmt>, label: Option<Identifier>) -> Result<LExpr, Error> {
+        let mut block2 = vec![];
+        for s in block {
+            block2.push(self.visit_stmt(s)?);
+        }
+        Ok(LExpr {
+            val: Expr::Loop(block2, label),
+            loc,
+        })
+    }
 }
@@ -1,11 +1,12 @@
 use std::fmt::Display;
 
-use crate::located::Located;
+use crate::{bigint::BigInt, located::Located};
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum TokenType {
-    Int(i32),
-    Float(f32),
+    Int(i64),
+    BigInt(BigInt), // does not fit into Int
+    Float(f64),
     Identifier(String),
     True,
     False,
@@ -22,6 +23,15 @@ pub enum TokenType {
     Struct,
     String(String),
     Impl,
+    Throw,
+    Trait,
+    For,
+    Const,
+    Yield,
+    In,
+    Loop,
+    Assert,
+    Label(String), // 'name
     // NOTE: EOF is needed as a buffer for some stuff in the parser
     // specifically for expressions, I think
     // probably not worth removing it, at least for now
@@ -37,6 +47,7 @@ pub enum TokenType {
     QuestionMark,
     Semicolon,
     Dot,
+    QuestionDot,
     Comma,
     Pipe,
 }
@@ -45,10 +56,12 @@ impl Display for TokenType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let s = match self {
             Self::Int(n) => n.to_string(),
+            Self::BigInt(n) => n.to_string(),
             Self::Float(n) => n.to_string(),
             Self::String(s) => format!("\"{s}\""),
             Self::Symbol(s) => s.to_string(),
             Self::Identifier(i) => i.to_string(),
+            Self::Label(l) => format!("'{l}"),
             typ => format!("{typ:?}"),
         };
         write!(f, "{s}")
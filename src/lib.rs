@@ -1,12 +1,13 @@
 use std::{collections::HashMap, time::Instant};
 
 use backend::interpreter::Interpreter;
-use backend::value::{get_builtins, NATIVE_OPERATORS};
+use backend::value::{get_builtins, operator_precedences};
 use error::Error;
 use located::Location;
 
 pub mod associativity;
 pub mod backend;
+pub mod bigint;
 pub mod environment;
 pub mod error;
 pub mod exprstmt;
@@ -38,13 +39,7 @@ pub fn run(interp: &mut Interpreter, input: &str, time: bool) -> Result<(), Vec<
     }
     */
 
-    let ast2 = frontend::reassoc::reassociate(
-        NATIVE_OPERATORS
-            .map(|(name, assoc, _)| (name.to_string(), assoc))
-            .into(),
-        ast,
-    )
-    .map_err(|e| vec![e])?;
+    let ast2 = frontend::reassoc::reassociate(operator_precedences(), ast).map_err(|e| vec![e])?;
     /*
     eprintln!("===== reassociating =====");
     for s in &resassoc {
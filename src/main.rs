@@ -1,9 +1,15 @@
-use moth_lang::backend::{interpreter::Interpreter, value::get_builtins};
+use moth_lang::backend::{
+    interpreter::{Capabilities, Interpreter},
+    value::get_builtins,
+};
+use moth_lang::error::{Error, ErrorType};
 use moth_lang::run;
 
 use std::{
     env, fs,
     io::{self, Write},
+    path::{Path, PathBuf},
+    process,
 };
 
 fn main() {
@@ -16,30 +22,131 @@ fn main() {
     let args = env::args().collect::<Vec<_>>();
     if args.len() == 1 {
         repl();
-    } else if args.len() == 2 {
+    } else if args[1] == "test" {
+        // a file named "test" has to be run as "./test"
+        if args.len() > 3 {
+            eprintln!("Usage: {} test [file or directory]", args[0]);
+            process::exit(1);
+        }
+        let path = args.get(2).map_or(".", |p| p.as_str());
+        if !test(Path::new(path)) {
+            process::exit(1);
+        }
+    } else {
         let file_name = &args[1];
-        let Ok(src) = fs::read_to_string(file_name) else {
-            eprintln!("File \"{file_name}\" not found.");
+        let Some(src) = read_file(Path::new(file_name)) else {
             return;
         };
-        // TODO: windows newlines have \r which messes up the lexer
-        let src = src.trim_end().replace('\r', "");
 
-        let mut interp = Interpreter::new(get_builtins());
+        let mut interp = Interpreter::new(get_builtins(), Capabilities::ALL);
+        // the rest is for the script
+        interp.set_args(args[2..].to_vec());
         if let Err(errs) = run(&mut interp, &src, true) {
+            exit_if_asked(&errs);
             for e in errs {
                 eprintln!("{}\n", e.format_message(&src));
             }
         }
+    }
+}
+
+/// ends the process if the program called exit, which unwinds as an error
+fn exit_if_asked(errs: &[Error]) {
+    if let [Error {
+        msg: ErrorType::Exit(code),
+        ..
+    }] = errs
+    {
+        // e.g. a prompt without a newline
+        io::stdout().flush().unwrap();
+        process::exit(*code);
+    }
+}
+
+fn read_file(path: &Path) -> Option<String> {
+    let Ok(src) = fs::read_to_string(path) else {
+        eprintln!("File \"{}\" not found.", path.display());
+        return None;
+    };
+    // TODO: windows newlines have \r which messes up the lexer
+    Some(src.trim_end().replace('\r', ""))
+}
+
+/// collects the files with moth code in the directory and its subdirectories
+fn find_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    let mut paths = entries.filter_map(|e| e.ok()).map(|e| e.path()).collect::<Vec<_>>();
+    paths.sort();
+    for path in paths {
+        if path.is_dir() {
+            find_files(&path, files);
+        } else if path.extension().is_some_and(|ext| ext == "moth") {
+            files.push(path);
+        }
+    }
+}
+
+/// runs the test blocks in the file, or in all ".moth" files in the directory
+/// returns whether everything passed
+fn test(path: &Path) -> bool {
+    let mut files = vec![];
+    if path.is_dir() {
+        find_files(path, &mut files);
     } else {
-        eprintln!("Unknown amount of arguments: {}", args.len());
+        files.push(path.to_path_buf());
+    }
+
+    let (mut passed, mut failed) = (0, 0);
+    let mut ok = true;
+    for file in files {
+        let Some(src) = read_file(&file) else {
+            ok = false;
+            continue;
+        };
+        println!("running {}", file.display());
+
+        // the code around the tests has to run first, as they may use anything declared in it
+        let mut interp = Interpreter::new(get_builtins(), Capabilities::ALL);
+        if let Err(errs) = run(&mut interp, &src, false) {
+            exit_if_asked(&errs);
+            for e in errs {
+                eprintln!("{}\n", e.format_message(&src));
+            }
+            ok = false;
+            continue;
+        }
+        for (name, res) in interp.run_tests() {
+            match res {
+                Ok(()) => {
+                    println!("test \"{name}\" ... ok");
+                    passed += 1;
+                }
+                // the rest of the tests is not run, like the rest of a program
+                Err(e @ Error {
+                    msg: ErrorType::Exit(_),
+                    ..
+                }) => {
+                    println!("test \"{name}\" ... exited");
+                    exit_if_asked(&[e]);
+                }
+                Err(e) => {
+                    println!("test \"{name}\" ... FAILED");
+                    eprintln!("{}\n", e.format_message(&src));
+                    failed += 1;
+                }
+            }
+        }
     }
+    println!("\n{passed} passed, {failed} failed");
+    ok && failed == 0
 }
 
 // TODO: declared things are not preserved between runs
 // caused by varcheck
 fn repl() {
-    let mut interp = Interpreter::new(get_builtins());
+    let mut interp = Interpreter::new(get_builtins(), Capabilities::ALL);
     loop {
         print!(">>> ");
         io::stdout().flush().unwrap(); // and  hope it never fails
@@ -50,6 +157,7 @@ fn repl() {
         match run(&mut interp, &input, false) {
             Ok(()) => {}
             Err(errs) => {
+                exit_if_asked(&errs);
                 for e in errs {
                     println!("{}", e.format_message(&input));
                 }
@@ -1,17 +1,25 @@
 #![allow(clippy::ptr_arg)]
 use crate::{
     associativity::Precedence,
+    backend::value::{NATIVE_OPERATORS, SYNTAX_OPERATORS},
+    bigint::BigInt,
     environment::Environment,
     error::{Error, ErrorType},
     exprstmt::*,
     located::Location,
 };
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 pub fn varcheck(builtins: HashMap<String, (Location, bool)>, stmt: &Vec<LStmt>) -> Result<(), (Vec<Error>, Vec<Error>)> {
     let mut var_check = VarCheck {
         env: Environment::new(builtins),
+        structs: HashMap::new(),
+        traits: HashMap::new(),
+        impls: HashMap::new(),
+        consts: HashSet::new(),
+        in_generator: false,
+        loops: vec![],
         errs: vec![],
         warns: vec![],
     };
@@ -25,10 +33,34 @@ pub fn varcheck(builtins: HashMap<String, (Location, bool)>, stmt: &Vec<LStmt>)
 
 struct VarCheck {
     env: Environment<(Location, bool)>,
+    // struct declarations, keyed by the location of their name
+    // this way shadowing a struct's name does not confuse the checks
+    // the fields and whether they have a default value
+    structs: HashMap<Location, Vec<(Identifier, bool)>>,
+    // same as structs; method name, parameter count and whether it has a default
+    traits: HashMap<Location, Vec<(Identifier, usize, bool)>>,
+    // items from impls of the struct declared at the location and whether they take self
+    impls: HashMap<Location, HashMap<String, bool>>,
+    // constants declared at the locations, which cannot be reassigned
+    consts: HashSet<Location>,
+    // whether yield is allowed
+    in_generator: bool,
+    // loops around the checked code, innermost last
+    loops: Vec<LoopInfo>,
     errs: Vec<Error>,
     warns: Vec<Error>,
 }
 
+struct LoopInfo {
+    label: Option<String>,
+    // only "loop" can break with a value
+    is_loop: bool,
+    // generators cannot stop in the middle of an expression
+    is_expression: bool,
+    // whether anything breaks or returns out of it
+    exits: bool,
+}
+
 // TODO: consider cutting down everything unused, e.g. brek and cont methods
 // as well as some unused parameters
 // these are leftovers of Visitor (which is no longer used), maybe they will come handy some time?
@@ -47,34 +79,80 @@ impl VarCheck {
             }
         };
     }
+    /// returns the fields of the struct bound to the name, if it is one
+    fn get_struct(&self, name: &String) -> Option<&Vec<(Identifier, bool)>> {
+        let (loc, _) = self.env.get(name)?;
+        self.structs.get(&loc)
+    }
+    /// whether the expression is statically known to be a struct instance
+    /// i.e. a direct call to a struct's constructor
+    fn is_struct_literal(&self, expr: &LExpr) -> bool {
+        match &expr.val {
+            Expr::Call(callee, _) => match &callee.val {
+                Expr::Identifier(name) => self.get_struct(name).is_some(),
+                _ => false,
+            },
+            Expr::StructLiteral(name, ..) => self.get_struct(&name.val).is_some(),
+            Expr::Parens(expr) => self.is_struct_literal(expr),
+            _ => false,
+        }
+    }
     fn check_block(&mut self, block: &Vec<LStmt>) {
         self.env.add_scope();
         for (i, s) in block.iter().enumerate() {
             match &s.val {
-                Stmt::VarDecl(t, expr) => {
+                Stmt::VarDecl(t, expr) | Stmt::Const(t, expr) => {
                     self.visit_expr(expr);
 
                     self.declare_item(&t.val, t.loc);
+                    if matches!(s.val, Stmt::Const(..)) {
+                        self.consts.insert(t.loc);
+                    }
                 }
-                Stmt::FunDecl(t, _, _) | Stmt::OperatorDecl(t, _, _, _) => {
+                Stmt::FunDecl(t, _, _) | Stmt::GenFunDecl(t, _, _) | Stmt::OperatorDecl(t, _, _, _) => {
                     self.declare_item(&t.val, t.loc);
 
                     self.visit_stmt(s);
                 }
                 Stmt::Assign(t, expr) => {
                     self.visit_expr(expr);
-                    if !self.env.contains(&t.val) {
-                        self.errs.push(Error {
+                    match self.env.get(&t.val) {
+                        None => self.errs.push(Error {
                             msg: ErrorType::UndeclaredItem,
                             lines: vec![s.loc],
-                        });
+                        }),
+                        Some((loc, _)) if self.consts.contains(&loc) => self.errs.push(Error {
+                            msg: ErrorType::AssignToConstant(t.val.clone()),
+                            lines: vec![s.loc, loc],
+                        }),
+                        Some(_) => {}
                     }
                 }
-                Stmt::Struct(name, _) => {
+                Stmt::Struct(name, fields) => {
                     self.declare_item(&name.val, name.loc);
+                    self.structs.insert(
+                        name.loc,
+                        fields.iter().map(|(f, default)| (f.clone(), default.is_some())).collect(),
+                    );
                     self.visit_stmt(s);
                 }
-                Stmt::Break | Stmt::Continue | Stmt::Return(_) => {
+                Stmt::Trait(name, required, defaults) => {
+                    self.declare_item(&name.val, name.loc);
+                    let mut methods = required
+                        .iter()
+                        .map(|(method, params)| (method.clone(), params.len(), false))
+                        .collect::<Vec<_>>();
+                    for d in defaults {
+                        let Stmt::FunDecl(method, params, _) = &d.val else {
+                            unreachable!("Checked in the parser");
+                        };
+                        methods.push((method.clone(), params.len(), true));
+                    }
+                    self.traits.insert(name.loc, methods);
+                    self.visit_stmt(s);
+                }
+                Stmt::Break(..) | Stmt::Continue(_) | Stmt::Return(_) | Stmt::Throw(_) => {
+                    self.visit_stmt(s);
                     if i == block.len() - 1 {
                         break;
                     }
@@ -91,9 +169,13 @@ impl VarCheck {
                 | Stmt::Block(..)
                 | Stmt::If(..)
                 | Stmt::While(..)
+                | Stmt::For(..)
+                | Stmt::Yield(..)
                 | Stmt::Expr(..)
                 | Stmt::Impl(..)
-                | Stmt::AssignStruct(..) => {
+                | Stmt::AssignStruct(..)
+                | Stmt::Assert(..)
+                | Stmt::Test(..) => {
                     self.visit_stmt(s);
                 }
             }
@@ -121,18 +203,32 @@ impl VarCheck {
             Stmt::AssignIndex(ls, idx, val) => self.assignindex(loc, ls, idx, val),
             Stmt::Block(block) => self.block(loc, block),
             Stmt::If(blocks, els) => self.if_else(loc, blocks, els),
-            Stmt::While(cond, block) => self.whiles(loc, cond, block),
+            Stmt::While(cond, block, label) => self.whiles(loc, cond, block, label),
             Stmt::FunDecl(name, params, block) => self.fun(loc, name, params, block),
+            Stmt::GenFunDecl(name, params, block) => self.gen_fun(loc, name, params, block),
             Stmt::OperatorDecl(name, params, block, prec) => self.operator(loc, name, params, block, prec),
             Stmt::Return(expr) => self.retur(loc, expr),
-            Stmt::Break => self.brek(loc),
-            Stmt::Continue => self.cont(loc),
+            Stmt::Break(label, expr) => self.brek(loc, label, expr),
+            Stmt::Continue(label) => self.cont(loc, label),
             Stmt::Struct(name, fields) => self.struc(loc, name, fields),
             Stmt::AssignStruct(expr1, name, expr2) => self.assignstruc(loc, expr1, name, expr2),
-            Stmt::Impl(name, block) => self.imp(loc, name, block),
+            Stmt::Impl(name, trait_name, block) => self.imp(loc, name, trait_name, block),
+            Stmt::Throw(expr) => self.throw(loc, expr),
+            Stmt::Trait(name, required, defaults) => self.trai(loc, name, required, defaults),
+            Stmt::Const(ident, expr) => self.cons(loc, ident, expr),
+            Stmt::Yield(expr) => self.yiel(loc, expr),
+            Stmt::For(ident, expr, block, label) => self.fors(loc, ident, expr, block, label),
+            Stmt::Assert(cond, msg) => self.asser(loc, cond, msg),
+            Stmt::Test(name, block) => self.test(loc, name, block),
         }
     }
     fn expr(&mut self, _: Location, expr: &LExpr) {
+        // the value of a loop statement is not used, so it is not an expression
+        if let Expr::Loop(block, label) = &expr.val {
+            let exits = self.check_loop(block, label, true, false);
+            self.warn_never_ends(exits, expr.loc);
+            return;
+        }
         self.visit_expr(expr);
     }
     fn var_decl(&mut self, _: Location, _: &Identifier, expr: &LExpr) {
@@ -170,19 +266,64 @@ impl VarCheck {
             self.check_block(bl);
         }
     }
-    fn whiles(&mut self, _: Location, cond: &LExpr, block: &Vec<LStmt>) {
+    fn whiles(&mut self, _: Location, cond: &LExpr, block: &Vec<LStmt>, label: &Option<Identifier>) {
         if cond.val == Expr::Bool(false) {
             self.warns.push(Error {
                 msg: ErrorType::LoopNeverExecutes,
                 lines: vec![cond.loc],
             });
         }
-        // TODO: do not forget about "true"
-        // but since I have no advanced checks yet (or loop loop), no need ig
         self.visit_expr(cond);
+        let exits = self.check_loop(block, label, false, false);
+        if cond.val == Expr::Bool(true) {
+            self.warn_never_ends(exits, cond.loc);
+        }
+    }
+    /// a loop which is not stopped by a condition runs forever when nothing gets out of it
+    fn warn_never_ends(&mut self, exits: bool, loc: Location) {
+        if !exits {
+            self.warns.push(Error {
+                msg: ErrorType::LoopNeverEnds,
+                lines: vec![loc],
+            });
+        }
+    }
+    /// returns whether anything gets out of the loop
+    fn check_loop(&mut self, block: &Vec<LStmt>, label: &Option<Identifier>, is_loop: bool, is_expression: bool) -> bool {
+        self.loops.push(LoopInfo {
+            label: label.as_ref().map(|l| l.val.clone()),
+            is_loop,
+            is_expression,
+            exits: false,
+        });
         self.check_block(block);
+        self.loops.pop().unwrap().exits
+    }
+    /// the index of the loop break or continue with the label belongs to
+    fn loop_target(&mut self, label: &Option<Identifier>) -> Option<usize> {
+        let Some(label) = label else {
+            // outside of a loop, which is reported by the interpreter
+            return self.loops.len().checked_sub(1);
+        };
+        let idx = self.loops.iter().rposition(|l| l.label.as_ref() == Some(&label.val));
+        if idx.is_none() {
+            self.errs.push(Error {
+                msg: ErrorType::UnknownLabel(label.val.clone()),
+                lines: vec![label.loc],
+            });
+        }
+        idx
     }
     fn fun(&mut self, _: Location, _: &Identifier, params: &Vec<Identifier>, block: &Vec<LStmt>) {
+        self.check_fun(params, block, false);
+    }
+    fn gen_fun(&mut self, _: Location, _: &Identifier, params: &Vec<Identifier>, block: &Vec<LStmt>) {
+        self.check_fun(params, block, true);
+    }
+    fn check_fun(&mut self, params: &Vec<Identifier>, block: &Vec<LStmt>, generator: bool) {
+        let in_generator = std::mem::replace(&mut self.in_generator, generator);
+        // loops outside cannot be broken out of
+        let loops = std::mem::take(&mut self.loops);
         let mut params2: HashMap<String, (Location, bool)> = HashMap::new();
         for p in params {
             let name = p.val.clone();
@@ -209,6 +350,8 @@ impl VarCheck {
             }
         }
         self.env.remove_scope();
+        self.in_generator = in_generator;
+        self.loops = loops;
     }
     fn operator(
         &mut self,
@@ -222,12 +365,72 @@ impl VarCheck {
     }
     fn retur(&mut self, _: Location, expr: &LExpr) {
         self.visit_expr(expr);
+        self.exit_loops();
+    }
+    fn exit_loops(&mut self) {
+        for l in &mut self.loops {
+            l.exits = true;
+        }
+    }
+    fn yiel(&mut self, loc: Location, expr: &LExpr) {
+        if !self.in_generator {
+            self.errs.push(Error {
+                msg: ErrorType::YieldOutsideGenerator,
+                lines: vec![loc],
+            });
+        } else if self.loops.iter().any(|l| l.is_expression) {
+            self.errs.push(Error {
+                msg: ErrorType::YieldInLoopExpression,
+                lines: vec![loc],
+            });
+        }
+        self.visit_expr(expr);
     }
-    fn brek(&mut self, _: Location) {}
-    fn cont(&mut self, _: Location) {}
-    fn struc(&mut self, _: Location, _: &Identifier, fields: &Vec<Identifier>) {
+    fn fors(&mut self, _: Location, ident: &Identifier, expr: &LExpr, block: &Vec<LStmt>, label: &Option<Identifier>) {
+        self.visit_expr(expr);
+        self.env.add_scope();
+        self.declare_item(&ident.val, ident.loc);
+        self.check_loop(block, label, false, false);
+        let (loc, used) = *self.env.scopes.last().unwrap().get(&ident.val).unwrap();
+        if !used {
+            self.warns.push(Error {
+                msg: ErrorType::ItemNotUsed(ident.val.clone()),
+                lines: vec![loc],
+            });
+        }
+        self.env.remove_scope();
+    }
+    fn brek(&mut self, _: Location, label: &Option<Identifier>, expr: &Option<LExpr>) {
+        if let Some(expr) = expr {
+            self.visit_expr(expr);
+        }
+        let Some(idx) = self.loop_target(label) else {
+            return;
+        };
+        // loops in between are left as well
+        for l in &mut self.loops[idx..] {
+            l.exits = true;
+        }
+        if let (Some(expr), false) = (expr, self.loops[idx].is_loop) {
+            self.errs.push(Error {
+                msg: ErrorType::BreakWithValue,
+                lines: vec![expr.loc],
+            });
+        }
+    }
+    fn cont(&mut self, _: Location, label: &Option<Identifier>) {
+        if let Some(idx) = self.loop_target(label) {
+            for l in &mut self.loops[idx + 1..] {
+                l.exits = true;
+            }
+        }
+    }
+    fn struc(&mut self, _: Location, _: &Identifier, fields: &Vec<(Identifier, Option<LExpr>)>) {
         let mut m: HashMap<String, Location> = HashMap::new();
-        for f in fields {
+        for (f, default) in fields {
+            if let Some(default) = default {
+                self.visit_expr(default);
+            }
             if let Some(field) = m.get(&f.val) {
                 self.errs.push(Error {
                     msg: ErrorType::DuplicateField(f.val.clone()),
@@ -242,15 +445,170 @@ impl VarCheck {
         self.visit_expr(expr1);
         self.visit_expr(expr2);
     }
-    fn imp(&mut self, _: Location, name: &Identifier, block: &Vec<LStmt>) {
+    fn imp(&mut self, _: Location, name: &Identifier, trait_name: &Option<Identifier>, block: &Vec<LStmt>) {
         if !self.env.contains(&name.val) {
             self.errs.push(Error {
                 msg: ErrorType::ImplWithoutStruct(name.val.clone()),
                 lines: vec![name.loc],
             });
         }
+        if let Some(trait_name) = trait_name {
+            self.check_trait_impl(name, trait_name, block);
+        }
+        if let Some((loc, _)) = self.env.get(&name.val) {
+            let items = self.impls.entry(loc).or_default();
+            for s in block {
+                match &s.val {
+                    Stmt::FunDecl(method, params, _) | Stmt::GenFunDecl(method, params, _) => {
+                        items.insert(method.val.clone(), params.first().is_some_and(|p| p.val == "self"));
+                    }
+                    Stmt::OperatorDecl(op, ..) => {
+                        items.insert(op.val.clone(), true);
+                    }
+                    Stmt::Const(name, _) => {
+                        items.insert(name.val.clone(), false);
+                    }
+                    _ => unreachable!("Checked in the parser"),
+                }
+            }
+        }
+        self.check_methods(block);
+    }
+    /// methods are not items in a scope, so they cannot be checked with check_block
+    /// (which would also complain about them not being used)
+    fn check_methods(&mut self, block: &Vec<LStmt>) {
+        let mut methods: HashMap<String, Location> = HashMap::new();
+        for s in block {
+            if let Stmt::OperatorDecl(op, ..) = &s.val {
+                // identity cannot be changed, and ?? is not a function since its default is not evaluated eagerly
+                if op.val == "is" || !NATIVE_OPERATORS.iter().any(|(name, _, _)| *name == op.val) {
+                    self.errs.push(Error {
+                        msg: ErrorType::NotOverloadable(op.val.clone()),
+                        lines: vec![op.loc],
+                    });
+                }
+            }
+            if let Stmt::FunDecl(method, ..)
+            | Stmt::GenFunDecl(method, ..)
+            | Stmt::OperatorDecl(method, ..)
+            | Stmt::Const(method, _) = &s.val
+            {
+                if let Some(original) = methods.get(&method.val) {
+                    self.errs.push(Error {
+                        msg: ErrorType::AlreadyDeclaredItem,
+                        lines: vec![*original, method.loc],
+                    });
+                } else {
+                    methods.insert(method.val.clone(), method.loc);
+                }
+            }
+            self.visit_stmt(s);
+        }
+    }
+    fn check_trait_impl(&mut self, name: &Identifier, trait_name: &Identifier, block: &Vec<LStmt>) {
+        let Some((loc, _)) = self.env.get(&trait_name.val) else {
+            self.errs.push(Error {
+                msg: ErrorType::UndeclaredItem,
+                lines: vec![trait_name.loc],
+            });
+            return;
+        };
+        self.env.update(&trait_name.val, (loc, true));
+        let Some(methods) = self.traits.get(&loc).cloned() else {
+            self.errs.push(Error {
+                msg: ErrorType::NotATrait(trait_name.val.clone()),
+                lines: vec![trait_name.loc],
+            });
+            return;
+        };
+
+        let implemented = block
+            .iter()
+            .filter_map(|s| match &s.val {
+                Stmt::FunDecl(method, params, _) | Stmt::GenFunDecl(method, params, _) => Some((method, params.len())),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        for (method, param_count, has_default) in &methods {
+            match implemented.iter().find(|(m, _)| m.val == method.val) {
+                Some((m, count)) if count != param_count => {
+                    self.errs.push(Error {
+                        msg: ErrorType::TraitMethodParameterCount(method.val.clone(), *count, *param_count),
+                        lines: vec![method.loc, m.loc],
+                    });
+                }
+                Some(_) => {}
+                None if !has_default => {
+                    self.errs.push(Error {
+                        msg: ErrorType::MissingTraitMethod(trait_name.val.clone(), method.val.clone()),
+                        lines: vec![name.loc, method.loc],
+                    });
+                }
+                None => {}
+            }
+        }
+        for (m, _) in implemented {
+            if !methods.iter().any(|(method, _, _)| method.val == m.val) {
+                self.errs.push(Error {
+                    msg: ErrorType::MethodNotInTrait(m.val.clone(), trait_name.val.clone()),
+                    lines: vec![m.loc],
+                });
+            }
+        }
+    }
+    fn cons(&mut self, _: Location, _: &Identifier, expr: &LExpr) {
+        self.visit_expr(expr);
+    }
+    fn throw(&mut self, _: Location, expr: &LExpr) {
+        self.visit_expr(expr);
+        self.exit_loops();
+    }
+    fn asser(&mut self, _: Location, cond: &LExpr, msg: &Option<LExpr>) {
+        self.visit_expr(cond);
+        if let Some(msg) = msg {
+            self.visit_expr(msg);
+        }
+    }
+    fn test(&mut self, _: Location, name: &Identifier, block: &Vec<LStmt>) {
+        // the builtins and the global scope
+        // tests run after the whole program, so they cannot be inside anything
+        if self.env.scopes.len() > 2 {
+            self.errs.push(Error {
+                msg: ErrorType::TestNotAtTopLevel(name.val.clone()),
+                lines: vec![name.loc],
+            });
+        }
         self.check_block(block);
     }
+    fn trai(
+        &mut self,
+        _: Location,
+        _: &Identifier,
+        required: &Vec<(Identifier, Vec<Identifier>)>,
+        defaults: &Vec<LStmt>,
+    ) {
+        let mut methods: HashMap<String, Location> = HashMap::new();
+        let names = required
+            .iter()
+            .map(|(name, _)| name)
+            .chain(defaults.iter().map(|s| match &s.val {
+                Stmt::FunDecl(name, _, _) => name,
+                _ => unreachable!("Checked in the parser"),
+            }));
+        for name in names {
+            if let Some(original) = methods.get(&name.val) {
+                self.errs.push(Error {
+                    msg: ErrorType::AlreadyDeclaredItem,
+                    lines: vec![*original, name.loc],
+                });
+            } else {
+                methods.insert(name.val.clone(), name.loc);
+            }
+        }
+        for s in defaults {
+            self.visit_stmt(s);
+        }
+    }
 }
 impl VarCheck {
     fn visit_expr(&mut self, expr: &LExpr) {
@@ -258,6 +616,7 @@ impl VarCheck {
         match &expr.val {
             Expr::Unit => self.unit(loc),
             Expr::Int(n) => self.int(loc, n),
+            Expr::BigInt(n) => self.bigint(loc, n),
             Expr::Float(n) => self.float(loc, n),
             Expr::String(s) => self.string(loc, s),
             Expr::Bool(b) => self.bool(loc, b),
@@ -271,12 +630,17 @@ impl VarCheck {
             Expr::Lambda(params, body) => self.lambda(loc, params, body),
             Expr::FieldAccess(expr, name) => self.field(loc, expr, name),
             Expr::MethodAccess(expr, name, args) => self.method(loc, expr, name, args),
+            Expr::SafeFieldAccess(expr, name) => self.safe_field(loc, expr, name),
+            Expr::SafeMethodAccess(expr, name, args) => self.safe_method(loc, expr, name, args),
+            Expr::StructLiteral(name, fields, base) => self.struct_literal(loc, name, fields, base),
+            Expr::Loop(block, label) => self.loops(loc, block, label),
         };
     }
     // nothing to check
     fn unit(&mut self, _: Location) {}
-    fn int(&mut self, _: Location, _: &i32) {}
-    fn float(&mut self, _: Location, _: &f32) {}
+    fn int(&mut self, _: Location, _: &i64) {}
+    fn bigint(&mut self, _: Location, _: &BigInt) {}
+    fn float(&mut self, _: Location, _: &f64) {}
     fn string(&mut self, _: Location, _: &String) {}
     fn bool(&mut self, _: Location, _: &bool) {}
     fn identifier(&mut self, loc: Location, ident: &String) {
@@ -295,6 +659,10 @@ impl VarCheck {
     fn parens(&mut self, _: Location, expr: &LExpr) {
         self.visit_expr(expr);
     }
+    fn loops(&mut self, loc: Location, block: &Vec<LStmt>, label: &Option<Identifier>) {
+        let exits = self.check_loop(block, label, true, true);
+        self.warn_never_ends(exits, loc);
+    }
     fn call(&mut self, _: Location, callee: &LExpr, args: &Vec<LExpr>) {
         self.visit_expr(callee);
         for arg in args {
@@ -307,14 +675,17 @@ impl VarCheck {
     fn binary(&mut self, loc: Location, left: &LExpr, op: &Symbol, right: &LExpr) {
         self.visit_expr(left);
         let s = &op.val;
-        match self.env.get(s) {
-            Some(var) => {
-                self.env.update(s, (var.0, true));
+        // these are evaluated by the interpreter itself, so there is no item to look up
+        if !SYNTAX_OPERATORS.iter().any(|(name, _)| name == s) {
+            match self.env.get(s) {
+                Some(var) => {
+                    self.env.update(s, (var.0, true));
+                }
+                None => self.errs.push(Error {
+                    msg: ErrorType::UndeclaredItem,
+                    lines: vec![loc],
+                }),
             }
-            None => self.errs.push(Error {
-                msg: ErrorType::UndeclaredItem,
-                lines: vec![loc],
-            }),
         }
         self.visit_expr(right);
     }
@@ -342,10 +713,93 @@ impl VarCheck {
         self.visit_expr(expr);
         // TODO: check for fields
     }
-    fn method(&mut self, _: Location, callee: &LExpr, _: &Identifier, args: &Vec<LExpr>) {
+    fn method(&mut self, _: Location, callee: &LExpr, name: &Identifier, args: &Vec<LExpr>) {
+        // methods taking self cannot be called on the struct itself
+        // only a struct given by its name can be told here, others are reported by the interpreter
+        let mut struct_ref = callee;
+        while let Expr::Parens(inner) = &struct_ref.val {
+            struct_ref = inner;
+        }
+        if let Expr::Identifier(struct_name) = &struct_ref.val {
+            let takes_self = self
+                .env
+                .get(struct_name)
+                .and_then(|(loc, _)| self.impls.get(&loc))
+                .and_then(|items| items.get(&name.val));
+            if takes_self == Some(&true) {
+                self.errs.push(Error {
+                    msg: ErrorType::MethodNeedsInstance(name.val.clone(), struct_name.clone()),
+                    lines: vec![callee.loc, name.loc],
+                });
+            }
+        }
         self.visit_expr(callee);
         for arg in args {
             self.visit_expr(arg);
         }
     }
+    fn safe_field(&mut self, loc: Location, expr: &LExpr, name: &Identifier) {
+        if self.is_struct_literal(expr) {
+            self.warns.push(Error {
+                msg: ErrorType::UnnecessarySafeAccess,
+                lines: vec![expr.loc],
+            });
+        }
+        self.field(loc, expr, name);
+    }
+    fn safe_method(&mut self, loc: Location, callee: &LExpr, name: &Identifier, args: &Vec<LExpr>) {
+        if self.is_struct_literal(callee) {
+            self.warns.push(Error {
+                msg: ErrorType::UnnecessarySafeAccess,
+                lines: vec![callee.loc],
+            });
+        }
+        self.method(loc, callee, name, args);
+    }
+    fn struct_literal(
+        &mut self,
+        _: Location,
+        name: &Identifier,
+        fields: &Vec<(Identifier, LExpr)>,
+        base: &Option<Box<LExpr>>,
+    ) {
+        self.identifier(name.loc, &name.val);
+        for (_, expr) in fields {
+            self.visit_expr(expr);
+        }
+        if let Some(base) = base {
+            self.visit_expr(base);
+        }
+        let Some(declared) = self.get_struct(&name.val).cloned() else {
+            return;
+        };
+        let mut m: HashMap<String, Location> = HashMap::new();
+        for (f, _) in fields {
+            if let Some(field) = m.get(&f.val) {
+                self.errs.push(Error {
+                    msg: ErrorType::DuplicateField(f.val.clone()),
+                    lines: vec![*field, f.loc],
+                });
+            } else if !declared.iter().any(|(d, _)| d.val == f.val) {
+                self.errs.push(Error {
+                    msg: ErrorType::UnknownField(f.val.clone()),
+                    lines: vec![f.loc],
+                });
+            } else {
+                m.insert(f.val.clone(), f.loc);
+            }
+        }
+        // the base provides all the remaining fields
+        if base.is_some() {
+            return;
+        }
+        for (d, has_default) in declared {
+            if !has_default && !m.contains_key(&d.val) {
+                self.errs.push(Error {
+                    msg: ErrorType::MissingField(d.val.clone(), name.val.clone()),
+                    lines: vec![name.loc, d.loc],
+                });
+            }
+        }
+    }
 }
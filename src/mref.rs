@@ -22,6 +22,20 @@ impl<T> MRef<T> {
             *(self.0.get()) = val;
         }
     }
+
+    pub fn replace(&mut self, val: T) -> T {
+        unsafe { std::mem::replace(&mut *self.0.get(), val) }
+    }
+
+    /// whether both point to the same value
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+
+    /// address of the value, usable as a key
+    pub fn addr(&self) -> usize {
+        Rc::as_ptr(&self.0) as *const () as usize
+    }
 }
 
 impl<T> From<T> for MRef<T> {
@@ -100,6 +114,35 @@ impl MList {
         }
     }
 
+    pub fn push(&mut self, val: Value) {
+        unsafe {
+            let ls = &mut *self.0.get();
+            ls.push(val);
+        }
+    }
+
+    pub fn pop(&mut self) -> Option<Value> {
+        unsafe {
+            let ls = &mut *self.0.get();
+            ls.pop()
+        }
+    }
+
+    /// the index can be the length, which appends it
+    pub fn insert(&mut self, idx: usize, val: Value) {
+        unsafe {
+            let ls = &mut *self.0.get();
+            ls.insert(idx, val);
+        }
+    }
+
+    pub fn remove(&mut self, idx: usize) -> Value {
+        unsafe {
+            let ls = &mut *self.0.get();
+            ls.remove(idx)
+        }
+    }
+
     // not necessary for now
     #[allow(clippy::len_without_is_empty)]
     pub fn len(&self) -> usize {
@@ -113,7 +156,7 @@ impl MList {
     // checks whether it is in the possible range (even if negative)
     // and returns it as a positive index
     // NOTE: for future me, this is also used for indexing strings
-    pub fn check_index(idx: i32, length: usize) -> Option<usize> {
+    pub fn check_index(idx: i64, length: usize) -> Option<usize> {
         if idx < 0 {
             if (idx.unsigned_abs() as usize) > length {
                 None
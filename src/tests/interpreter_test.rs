@@ -1,16 +1,20 @@
-use crate::backend::interpreter::Interpreter;
-use crate::backend::value::{get_builtins, ValueType};
+use crate::backend::interpreter::{Capabilities, Interpreter};
+use crate::backend::simplify::simplify;
+use crate::backend::value::{get_builtins, operator_precedences, NativeFunction, ValueType};
+use crate::error::{Error, ErrorType};
+use crate::frontend::{lexer, parser, reassoc::reassociate};
+use crate::located::Location;
 use crate::run;
 
 fn run_code(code: &str, val: &str) -> Option<ValueType> {
-    let mut interp = Interpreter::new(get_builtins());
+    let mut interp = Interpreter::new(get_builtins(), Capabilities::ALL);
     run(&mut interp, code, false).ok()?;
     interp.get_val(val.to_string())
 }
 
 #[test]
 fn blank() {
-    let mut interp = Interpreter::new(get_builtins());
+    let mut interp = Interpreter::new(get_builtins(), Capabilities::ALL);
     let res = run(&mut interp, "", false);
     assert_eq!(res.is_ok(), true);
 }
@@ -34,7 +38,7 @@ fn list() {
 
 #[test]
 fn t() {
-    let mut interp = Interpreter::new(get_builtins());
+    let mut interp = Interpreter::new(get_builtins(), Capabilities::ALL);
     run(&mut interp, "let x = 2;", false).unwrap();
     assert_eq!(ValueType::Int(2), interp.get_val("x".to_string()).unwrap());
 }
@@ -164,3 +168,1759 @@ let x = p.manhattan();
         Some(ValueType::Int(9))
     );
 }
+
+#[test]
+fn throw_through_functions() {
+    let mut interp = Interpreter::new(get_builtins(), Capabilities::ALL);
+    let res = run(
+        &mut interp,
+        "
+fun inner(x) {
+    while true {
+        throw x + 1;
+    }
+}
+fun outer() {
+    inner(1);
+}
+outer();
+        ",
+        false,
+    );
+    assert_eq!(
+        res,
+        Err(vec![Error {
+            msg: ErrorType::UncaughtThrow(ValueType::Int(2)),
+            lines: vec![Location { start: 41, end: 51 }]
+        }])
+    );
+}
+
+#[test]
+fn panic() {
+    let mut interp = Interpreter::new(get_builtins(), Capabilities::ALL);
+    let res = run(&mut interp, "panic(\"oops\");", false);
+    assert_eq!(
+        res,
+        Err(vec![Error {
+            msg: ErrorType::NativeFunctionError("Panicked: oops".to_string()),
+            lines: vec![Location { start: 0, end: 12 }]
+        }])
+    );
+}
+
+#[test]
+fn safe_access() {
+    assert_eq!(
+        run_code(
+            "
+struct Point { x, y }
+impl Point {
+    fun sum(self) {
+        return self.x + self.y;
+    }
+}
+let p = some(Point(1, 2));
+let n = none;
+let x = [p?.x, p?.sum(), n?.x, n?.sum(), n?.y ?? 10, 5 ?? 3, none ?? none ?? 7];
+            ",
+            "x"
+        )
+        .map(|x| x.to_string()),
+        Some("[1, 3, none, none, 10, 5, 7]".to_string())
+    );
+}
+
+#[test]
+fn coalesce_is_lazy() {
+    assert_eq!(
+        run_code(
+            "
+let calls = 0;
+fun fallback() {
+    calls = calls + 1;
+    return 10;
+}
+let x = [some(1) ?? fallback(), 2 ?? panic(\"missing\"), none ?? fallback(), calls];
+            ",
+            "x"
+        )
+        .map(|x| x.to_string()),
+        Some("[1, 2, 10, 1]".to_string())
+    );
+}
+
+#[test]
+fn coalesce_is_not_a_function() {
+    let mut interp = Interpreter::new(get_builtins(), Capabilities::ALL);
+    // as a function, the default would be evaluated eagerly
+    assert_eq!(
+        run(&mut interp, "let f = (??);", false),
+        Err(vec![Error {
+            msg: ErrorType::UndeclaredItem,
+            lines: vec![Location { start: 8, end: 11 }]
+        }])
+    );
+}
+
+#[test]
+fn traits() {
+    assert_eq!(
+        run_code(
+            "
+trait Show {
+    fun show(self);
+    fun debug(self) {
+        return \"<\" + self.show() + \">\";
+    }
+    fun name(self) {
+        return \"default\";
+    }
+}
+struct Point { x, y }
+impl Show for Point {
+    fun show(self) {
+        return \"Point\";
+    }
+    fun name(self) {
+        return \"overriden\";
+    }
+}
+let p = Point(1, 2);
+let x = [p.debug(), p.name()];
+            ",
+            "x"
+        )
+        .map(|x| x.to_string()),
+        Some("[\"<Point>\", \"overriden\"]".to_string())
+    );
+}
+
+#[test]
+fn operator_overloading() {
+    assert_eq!(
+        run_code(
+            "
+struct Vector { x, y }
+impl Vector {
+    fun +(self, other) {
+        return Vector(self.x + other.x, self.y + other.y);
+    }
+    fun ==(self, other) {
+        return (self.x == other.x) && (self.y == other.y);
+    }
+    fun <(self, other) {
+        return self.x < other.x;
+    }
+}
+let v = Vector(1, 2) + Vector(3, 4);
+let x = [v.x, v.y, v == Vector(4, 6), v != Vector(4, 6), v < Vector(5, 0), v > Vector(5, 0), v <= Vector(4, 6), 1 + 2];
+            ",
+            "x"
+        )
+        .map(|x| x.to_string()),
+        Some("[4, 6, true, false, true, false, true, 3]".to_string())
+    );
+}
+
+#[test]
+fn operator_overloading_left_only() {
+    // the method is only looked up on the left operand, the native operator gets the rest
+    let mut interp = Interpreter::new(get_builtins(), Capabilities::ALL);
+    let code = "
+struct Vector { x }
+impl Vector {
+    fun +(self, other) {
+        return Vector(self.x + other);
+    }
+}
+let x = (Vector(1) + 1).x;
+1 + Vector(1);
+    ";
+    let res = run(&mut interp, code, false);
+    assert!(
+        matches!(&res, Err(errs) if errs[0].lines == vec![Location { start: 136, end: 136 }]
+            && matches!(&errs[0].msg, ErrorType::NativeFunctionError(msg) if msg.starts_with("Invalid values: \"1\" and \"Vector("))),
+        "{res:?}"
+    );
+    assert_eq!(interp.get_val("x".to_string()), Some(ValueType::Int(2)));
+}
+
+#[test]
+fn associated_functions() {
+    assert_eq!(
+        run_code(
+            "
+struct Point { x, y }
+impl Point {
+    const ZERO = 0;
+    fun origin() {
+        return Point(Point.ZERO, Point.ZERO);
+    }
+    fun new(x, y) {
+        return Point(x, y);
+    }
+    fun sum(self) {
+        return self.x + self.y;
+    }
+}
+let o = Point.origin();
+let x = [o.x, o.y, Point.new(1, 2).sum(), Point.ZERO];
+            ",
+            "x"
+        )
+        .map(|x| x.to_string()),
+        Some("[0, 0, 3, 0]".to_string())
+    );
+}
+
+#[test]
+fn method_needs_instance_at_runtime() {
+    let mut interp = Interpreter::new(get_builtins(), Capabilities::ALL);
+    // varcheck cannot tell what the variable holds, so the interpreter reports it
+    let code = "
+struct S { x }
+impl S {
+    fun get(self) { return self.x; }
+}
+let T = S;
+T.get();
+    ";
+    assert_eq!(
+        run(&mut interp, code, false),
+        Err(vec![Error {
+            msg: ErrorType::MethodNeedsInstance("get".to_string(), "S".to_string()),
+            lines: vec![Location { start: 75, end: 75 }, Location { start: 77, end: 79 }]
+        }])
+    );
+}
+
+#[test]
+fn named_structs() {
+    assert_eq!(
+        run_code(
+            "
+struct Point { x, y = 0, tags = [0] }
+let a = Point { y: 2, x: 1 };
+let b = Point { x: 3, ..a };
+let c = Point(4);
+c.tags[0] = 1;
+let x = [a.x, a.y, b.x, b.y, c.x, c.y, a.tags[0], c.tags[0]];
+if (Point { x: 1 }).x == 1 {}
+            ",
+            "x"
+        )
+        .map(|x| x.to_string()),
+        Some("[1, 2, 3, 2, 4, 0, 0, 1]".to_string())
+    );
+}
+
+#[test]
+fn instance_methods() {
+    let code = "
+struct Point { x }
+let p = Point(1);
+impl Point {
+    fun double(self) { return self.x * 2; }
+}
+let x = [p, p.double(), p == Point(1)];
+fun other() {
+    struct Point { x, y }
+    return Point { y: 2, ..p };
+}
+other();
+    ";
+    let mut interp = Interpreter::new(get_builtins(), Capabilities::ALL);
+    // a struct with the same name is still a different struct
+    assert_eq!(
+        run(&mut interp, code, false),
+        Err(vec![Error {
+            msg: ErrorType::ExpectedInstanceOf("Point".to_string()),
+            lines: vec![Location { start: 204, end: 204 }]
+        }])
+    );
+    // methods added after the instance was created are found too, and are not fields
+    assert_eq!(
+        interp.get_val("x".to_string()).map(|x| x.to_string()),
+        Some("[Point({ x: 1 }), 2, true]".to_string())
+    );
+}
+
+#[test]
+fn deep_equality() {
+    assert_eq!(
+        run_code(
+            "
+struct P { x, y }
+let a = [1];
+a[0] = a;
+let b = [1];
+b[0] = b;
+let p = P(1, [2]);
+let x = [
+    [1, [2, 3]] == [1, [2, 3]],
+    [1, 2] == [1, 3],
+    P(1, [2]) == p,
+    P(1, [2]) is p,
+    p is p,
+    a == b,
+    a is b,
+    1 == \"1\",
+    () == (),
+    some([1]) == some([1]),
+];
+            ",
+            "x"
+        )
+        .map(|x| x.to_string()),
+        Some("[true, false, true, false, true, true, false, false, true, true]".to_string())
+    );
+}
+
+#[test]
+fn ordering() {
+    assert_eq!(
+        run_code(
+            "
+let x = [[1, 2] < [1, 3], [1, 2] < [1], [] < [0], \"abc\" < \"abd\", \"b\" > \"abc\", [\"a\", 2] <= [\"a\", 2]];
+            ",
+            "x"
+        )
+        .map(|x| x.to_string()),
+        Some("[true, false, true, true, true, true]".to_string())
+    );
+}
+
+#[test]
+fn generators() {
+    assert_eq!(
+        run_code(
+            "
+fun* range(start, end) {
+    let i = start;
+    while i < end {
+        yield i;
+        i = i + 1;
+    }
+}
+fun* evens(gen) {
+    for n in gen {
+        if n % 2 == 0 {
+            yield n;
+        }
+        if n > 6 {
+            break;
+        }
+    }
+    yield -1;
+}
+let x = [];
+for n in evens(range(0, 100)) {
+    x = x + [n];
+}
+let g = range(0, 1);
+x = x + [g.next(), g.next(), g.next()];
+            ",
+            "x"
+        )
+        .map(|x| x.to_string()),
+        Some("[0, 2, 4, 6, -1, some(0), none, none]".to_string())
+    );
+}
+
+#[test]
+fn iterator_protocol() {
+    assert_eq!(
+        run_code(
+            "
+struct Countdown { n }
+impl Countdown {
+    fun next(self) {
+        if self.n == 0 {
+            return none;
+        }
+        self.n = self.n - 1;
+        return some(self.n);
+    }
+}
+struct Bag { items }
+impl Bag {
+    fun* iter(self) {
+        for i in self.items {
+            yield i;
+        }
+    }
+}
+let x = [];
+for n in Countdown(3) {
+    x = x + [n];
+}
+for i in Bag([\"a\", \"b\"]) {
+    x = x + [i];
+}
+for c in \"hi\" {
+    x = x + [c];
+}
+            ",
+            "x"
+        )
+        .map(|x| x.to_string()),
+        Some("[2, 1, 0, \"a\", \"b\", \"h\", \"i\"]".to_string())
+    );
+}
+
+#[test]
+fn tail_calls() {
+    assert_eq!(
+        run_code(
+            "
+fun count(n, acc) {
+    if n == 0 {
+        return acc;
+    }
+    return count(n - 1, acc + 2);
+}
+fun is_even(n, even) {
+    while true {
+        if n == 0 { return even; }
+        return is_even(n - 1, !even);
+    }
+}
+struct Counter { n }
+impl Counter {
+    fun down(self, steps) {
+        if steps == 0 { return self.n; }
+        self.n = self.n - 1;
+        return self.down(steps - 1);
+    }
+}
+let x = [count(1000000, 0), is_even(100001, true), Counter(0).down(100000)];
+            ",
+            "x"
+        )
+        .map(|x| x.to_string()),
+        Some("[2000000, false, -100000]".to_string())
+    );
+}
+
+#[test]
+fn returned_operators() {
+    // operators are not tail calls, even when they are overloaded or declared by the user
+    assert_eq!(
+        run_code(
+            "
+struct V { x }
+impl V {
+    fun +(self, other) { return V(self.x + other.x); }
+}
+fun <+>(a, b) { return a * 10 + b; }
+fun add(a, b) { return a + b; }
+fun neg(n) { return -n; }
+fun join(a, b) { return a <+> b; }
+let x = [add(V(1), V(2)).x, add(1, 2), neg(3), join(1, 2)];
+            ",
+            "x"
+        )
+        .map(|x| x.to_string()),
+        Some("[3, 3, -3, 12]".to_string())
+    );
+}
+
+#[test]
+fn labeled_loops() {
+    assert_eq!(
+        run_code(
+            "
+let x = [];
+'outer: for i in [1, 2, 3] {
+    let j = 0;
+    while j < 3 {
+        j = j + 1;
+        if j == 2 { continue 'outer; }
+        if i == 3 { break 'outer; }
+        x = x + [[i, j]];
+    }
+}
+            ",
+            "x"
+        )
+        .map(|x| x.to_string()),
+        Some("[[1, 1], [2, 1]]".to_string())
+    );
+}
+
+#[test]
+fn loop_expression() {
+    assert_eq!(
+        run_code(
+            "
+let n = 0;
+let x = [loop {
+    n = n + 1;
+    if n == 5 { break n * 10; }
+}];
+fun find(ls) {
+    let r = loop {
+        for v in ls {
+            if v % 2 == 0 { return v; }
+        }
+        break -1;
+    };
+    return r;
+}
+x = x + [find([1, 3, 4]), find([1])];
+'a: loop {
+    let y = 'b: loop {
+        n = n + 1;
+        if n > 7 { break 'a; }
+        break 'b n;
+    };
+    x = x + [y];
+}
+fun* gen() {
+    let i = 0;
+    'o: loop {
+        while true {
+            i = i + 1;
+            if i > 3 { break 'o; }
+            yield i;
+            continue 'o;
+        }
+    }
+}
+for v in gen() {
+    x = x + [v];
+}
+            ",
+            "x"
+        )
+        .map(|x| x.to_string()),
+        Some("[50, 4, -1, 6, 7, 1, 2, 3]".to_string())
+    );
+}
+
+#[test]
+fn loop_expressions_in_generators() {
+    // return and break leave a loop expression the same way as in a regular function
+    let code = "
+fun* values(stop) {
+    yield loop { break 1; };
+    yield loop { if stop == 1 { return 0; } break 2; };
+    if loop { if stop == 2 { return 0; } break true; } {
+        yield 3;
+    }
+    while loop { if stop == 3 { return 0; } break false; } {
+        yield 0;
+    }
+    for v in loop { if stop == 4 { return 0; } break [4]; } {
+        yield v;
+    }
+}
+fun collect(gen) {
+    let res = [];
+    for v in gen {
+        res = res + [v];
+    }
+    return res;
+}
+let x = [collect(values(0)), collect(values(1)), collect(values(2)), collect(values(3)), collect(values(4))];
+    ";
+    assert_eq!(
+        run_code(code, "x").map(|x| x.to_string()),
+        Some("[[1, 2, 3, 4], [1], [1, 2], [1, 2, 3], [1, 2, 3]]".to_string())
+    );
+}
+
+#[test]
+fn constants() {
+    assert_eq!(
+        run_code(
+            "
+const KB = 1024;
+const MAX = 10 * KB;
+const NAME = \"moth\" + \"lang\";
+const NEG = -(MAX / 2) % 3;
+struct Limits {}
+impl Limits {
+    const MAX = MAX + 1;
+}
+fun f(KB) {
+    return KB + MAX;
+}
+let x = [MAX, NAME, NEG, Limits.MAX, f(1)];
+{
+    let MAX = 3;
+    x = x + [MAX];
+}
+x = x + [MAX, !(KB > 2)];
+            ",
+            "x"
+        )
+        .map(|x| x.to_string()),
+        Some("[10240, \"mothlang\", -2, 10241, 10241, 3, 10240, false]".to_string())
+    );
+}
+
+#[test]
+fn not_constant() {
+    let mut interp = Interpreter::new(get_builtins(), Capabilities::ALL);
+    let res = run(&mut interp, "let x = 2; const A = 1 + 3 * x; print(A);", false);
+    assert_eq!(
+        res,
+        Err(vec![Error {
+            msg: ErrorType::NotConstant,
+            lines: vec![Location { start: 29, end: 29 }]
+        }])
+    );
+}
+
+#[test]
+fn asserts() {
+    let mut interp = Interpreter::new(get_builtins(), Capabilities::ALL);
+    let res = run(&mut interp, "assert 1 + 1 == 2; let x = 1; assert x > 2, \"too small\";", false);
+    assert_eq!(
+        res,
+        Err(vec![Error {
+            msg: ErrorType::AssertionFailed(Some("too small".to_string())),
+            lines: vec![Location { start: 37, end: 41 }]
+        }])
+    );
+}
+
+#[test]
+fn test_blocks() {
+    let mut interp = Interpreter::new(get_builtins(), Capabilities::ALL);
+    // tests run after the whole program
+    let code = "
+let x = 1;
+test \"before\" {
+    let y = x + 1;
+    assert y == 2;
+}
+test \"after\" {
+    assert x == 2;
+}
+x = 2;
+    ";
+    run(&mut interp, code, false).unwrap();
+    assert_eq!(
+        interp.run_tests(),
+        vec![
+            (
+                "before".to_string(),
+                Err(Error {
+                    msg: ErrorType::AssertionFailed(None),
+                    lines: vec![Location { start: 58, end: 63 }]
+                })
+            ),
+            ("after".to_string(), Ok(())),
+        ]
+    );
+    // they are not run again
+    assert_eq!(interp.run_tests(), vec![]);
+}
+
+#[test]
+fn test_blocks_exit() {
+    let mut interp = Interpreter::new(get_builtins(), Capabilities::ALL);
+    let code = "
+test \"first\" {
+    exit(0);
+}
+test \"second\" {
+    assert false;
+}
+    ";
+    run(&mut interp, code, false).unwrap();
+    // exit is not a failure, and the tests after it are not run
+    assert_eq!(
+        interp.run_tests(),
+        vec![(
+            "first".to_string(),
+            Err(Error {
+                msg: ErrorType::Exit(0),
+                lines: vec![Location { start: 20, end: 26 }]
+            })
+        )]
+    );
+}
+
+#[test]
+fn big_integers() {
+    assert_eq!(
+        run_code(
+            "
+fun fact(n) {
+    let r = 1;
+    while n > 1 {
+        r = r * n;
+        n = n - 1;
+    }
+    return r;
+}
+let max = 9223372036854775807;
+let min = -max - 1;
+let f = fact(30);
+let x = [
+    max + 1, min - 1, -min, min / -1, min % -1, max * max,
+    f, f / fact(28), f % 1000000007, -f / 7, -f % 31,
+    123456789012345678901234567890 / 98765432109876543210,
+    max + 1 - 1, f - f, f > max, f == fact(30), [f] == [265252859812191058636308480000000],
+];
+            ",
+            "x"
+        )
+        .map(|x| x.to_string()),
+        Some(
+            "[9223372036854775808, -9223372036854775809, 9223372036854775808, 9223372036854775808, 0, \
+            85070591730234615847396907784232501249, 265252859812191058636308480000000, 870, 109361473, \
+            -37893265687455865519472640000000, -30, 1249999988, 9223372036854775807, 0, true, true, true]"
+                .to_string()
+        )
+    );
+}
+
+#[test]
+fn modulo_by_zero() {
+    let mut interp = Interpreter::new(get_builtins(), Capabilities::ALL);
+    assert_eq!(
+        run(&mut interp, "let x = 0; 5 % x;", false),
+        Err(vec![Error {
+            msg: ErrorType::NativeFunctionError("Attempted division by zero".to_string()),
+            lines: vec![Location { start: 13, end: 13 }]
+        }])
+    );
+}
+
+#[test]
+fn big_division_by_zero() {
+    let mut interp = Interpreter::new(get_builtins(), Capabilities::ALL);
+    assert_eq!(
+        run(&mut interp, "let x = 0; 92233720368547758070 / x;", false),
+        Err(vec![Error {
+            msg: ErrorType::NativeFunctionError("Attempted division by zero".to_string()),
+            lines: vec![Location { start: 32, end: 32 }]
+        }])
+    );
+}
+
+#[test]
+fn numeric_conversions() {
+    assert_eq!(
+        run_code(
+            "
+let x = [
+    1.0, 0.1 + 0.2, 123456789.125, 100000000000000000000.0,
+    int(2.7), int(-2.7), round(2.5), round(-2.5), floor(-2.5), ceil(2.1), int(5),
+    float(3), float(2) / 3.0, int(100000000000000000000.0), float(100000000000000000000),
+];
+            ",
+            "x"
+        )
+        .map(|x| x.to_string()),
+        Some(
+            "[1.0, 0.30000000000000004, 123456789.125, 100000000000000000000.0, \
+            2, -2, 3, -3, -3, 3, 5, \
+            3.0, 0.6666666666666666, 100000000000000000000, 100000000000000000000.0]"
+                .to_string()
+        )
+    );
+}
+
+#[test]
+fn int_of_nan() {
+    let mut interp = Interpreter::new(get_builtins(), Capabilities::ALL);
+    assert_eq!(
+        run(&mut interp, "int(0.0 / 0.0);", false),
+        Err(vec![Error {
+            msg: ErrorType::NativeFunctionError("Cannot convert NaN to an integer".to_string()),
+            lines: vec![Location { start: 0, end: 13 }]
+        }])
+    );
+}
+
+#[test]
+fn floor_of_inf() {
+    let mut interp = Interpreter::new(get_builtins(), Capabilities::ALL);
+    assert_eq!(
+        run(&mut interp, "floor(1.0 / 0.0);", false),
+        Err(vec![Error {
+            msg: ErrorType::NativeFunctionError("Cannot convert inf to an integer".to_string()),
+            lines: vec![Location { start: 0, end: 15 }]
+        }])
+    );
+}
+
+#[test]
+fn int_of_string() {
+    let mut interp = Interpreter::new(get_builtins(), Capabilities::ALL);
+    assert_eq!(
+        run(&mut interp, "int(\"1\");", false),
+        Err(vec![Error {
+            msg: ErrorType::NativeFunctionError("Expected a number, got: \"1\"".to_string()),
+            lines: vec![Location { start: 0, end: 7 }]
+        }])
+    );
+}
+
+#[test]
+fn list_functions() {
+    assert_eq!(
+        run_code(
+            "
+let ls = [1, 2, 3];
+push(ls, 4);
+insert(ls, 0, 0);
+insert(ls, 5, 5);
+let popped = pop(ls);
+let removed = remove(ls, -1);
+let doubled = map(ls, |x| x * 2);
+let odd = filter(ls, |x| x % 2 == 1);
+let sum = fold(ls, 0, |acc, x| acc + x);
+reverse(ls);
+let pairs = [[2, \"b\"], [1, \"a\"], [2, \"a\"], [1, \"b\"]];
+sort(pairs, |a, b| a[0] < b[0]);
+let words = [\"b\", \"c\", \"a\"];
+sort(words);
+let x = [
+    ls, popped, removed, doubled, odd, sum, pairs, words,
+    any(ls, |x| x > 2), all(ls, |x| x > 2), contains(ls, 2), index_of(ls, 2), index_of(ls, 10),
+];
+            ",
+            "x"
+        )
+        .map(|x| x.to_string()),
+        Some(
+            "[[3, 2, 1, 0], 5, 4, [0, 2, 4, 6], [1, 3], 6, \
+            [[1, \"a\"], [1, \"b\"], [2, \"b\"], [2, \"a\"]], [\"a\", \"b\", \"c\"], \
+            true, false, true, some(1), none]"
+                .to_string()
+        )
+    );
+}
+
+#[test]
+fn pop_empty_list() {
+    let mut interp = Interpreter::new(get_builtins(), Capabilities::ALL);
+    assert_eq!(
+        run(&mut interp, "pop([]);", false),
+        Err(vec![Error {
+            msg: ErrorType::NativeFunctionError("Cannot pop from an empty list".to_string()),
+            lines: vec![Location { start: 0, end: 6 }]
+        }])
+    );
+}
+
+#[test]
+fn insert_out_of_range() {
+    let mut interp = Interpreter::new(get_builtins(), Capabilities::ALL);
+    assert_eq!(
+        run(&mut interp, "insert([1], 2, 0);", false),
+        Err(vec![Error {
+            msg: ErrorType::NativeFunctionError("Index out of range: 2 (length 1)".to_string()),
+            lines: vec![Location { start: 0, end: 16 }]
+        }])
+    );
+}
+
+#[test]
+fn filter_without_bool() {
+    let mut interp = Interpreter::new(get_builtins(), Capabilities::ALL);
+    assert_eq!(
+        run(&mut interp, "filter([1], |x| x);", false),
+        Err(vec![Error {
+            msg: ErrorType::NativeFunctionError("Expected a bool, got: 1".to_string()),
+            lines: vec![Location { start: 0, end: 17 }]
+        }])
+    );
+}
+
+#[test]
+fn sort_incomparable() {
+    let mut interp = Interpreter::new(get_builtins(), Capabilities::ALL);
+    assert_eq!(
+        run(&mut interp, "sort([1, 1.5]);", false),
+        Err(vec![Error {
+            msg: ErrorType::NativeFunctionError("Invalid values: \"1.5\" and \"1\"".to_string()),
+            lines: vec![Location { start: 0, end: 13 }]
+        }])
+    );
+}
+
+#[test]
+fn map_error_inside() {
+    let mut interp = Interpreter::new(get_builtins(), Capabilities::ALL);
+    // errors inside the function are reported where they happen
+    assert_eq!(
+        run(&mut interp, "map([1], |x| x / 0);", false),
+        Err(vec![Error {
+            msg: ErrorType::NativeFunctionError("Attempted division by zero".to_string()),
+            lines: vec![Location { start: 15, end: 15 }]
+        }])
+    );
+}
+
+#[test]
+fn list_functions_with_overloads() {
+    // the operators are used the same way as in the code, so instances can overload them
+    let code = "
+struct Version { major, minor }
+impl Version {
+    fun <(self, other) {
+        return (self.major < other.major) || ((self.major == other.major) && (self.minor < other.minor));
+    }
+    fun ==(self, other) {
+        return self.major == other.major;
+    }
+}
+let ls = [Version(2, 0), Version(1, 5), Version(1, 2)];
+sort(ls);
+let x = [
+    map(ls, |v| v.minor), contains(ls, Version(2, 7)), contains(ls, Version(3, 0)),
+    index_of(ls, Version(1, 9)), index_of([1, 2], 2),
+];
+    ";
+    assert_eq!(
+        run_code(code, "x").map(|x| x.to_string()),
+        Some("[[2, 5, 0], true, false, some(0), some(1)]".to_string())
+    );
+}
+
+#[test]
+fn list_functions_on_iterables() {
+    let code = "
+fun* count(n) {
+    let i = 0;
+    while i < n {
+        yield i;
+        i = i + 1;
+    }
+}
+let g = count(1000000000);
+let x = [
+    map(count(3), |n| n * 2), filter(\"abc\", |c| c != \"b\"), fold(count(4), 0, |acc, n| acc + n),
+    any(g, |n| n == 2), g.next(), all(count(0), |_| false),
+];
+    ";
+    // any stops early, so the rest of the generator is left
+    assert_eq!(
+        run_code(code, "x").map(|x| x.to_string()),
+        Some("[[0, 2, 4], [\"a\", \"c\"], 6, true, some(3), true]".to_string())
+    );
+    let mut interp = Interpreter::new(get_builtins(), Capabilities::ALL);
+    assert_eq!(
+        run(&mut interp, "map(1, |n| n);", false),
+        Err(vec![Error {
+            msg: ErrorType::NotIterable("1".to_string()),
+            lines: vec![Location { start: 0, end: 12 }]
+        }])
+    );
+}
+
+#[test]
+fn len_of_generator() {
+    let mut interp = Interpreter::new(get_builtins(), Capabilities::ALL);
+    // counting would use the generator up, so it cannot be done
+    assert_eq!(
+        run(&mut interp, "fun* g() { yield 1; } len(g());", false),
+        Err(vec![Error {
+            msg: ErrorType::NativeFunctionError("Invalid value: <generator>".to_string()),
+            lines: vec![Location { start: 22, end: 29 }]
+        }])
+    );
+}
+
+#[test]
+fn contains_on_generator() {
+    let mut interp = Interpreter::new(get_builtins(), Capabilities::ALL);
+    // searching would use the generator up too, so only lists are taken
+    assert_eq!(
+        run(&mut interp, "fun* g() { yield 1; } contains(g(), 1);", false),
+        Err(vec![Error {
+            msg: ErrorType::NativeFunctionError("Expected a list, got: <generator>".to_string()),
+            lines: vec![Location { start: 22, end: 37 }]
+        }])
+    );
+}
+
+#[test]
+fn string_functions() {
+    assert_eq!(
+        run_code(
+            "
+let s = \"  Příliš žluťoučký kůň  \";
+let x = [
+    len(\"žluť\"), \"žluť\"[-1], trim(s), upper(\"kůň\"), lower(\"ÚPĚL\"),
+    split(s), split(\"a,b,,c\", \",\"), join([\"a\", \"b\", \"c\"], \"-\"), chars(\"kůň\"),
+    starts_with(\"kůň\", \"ků\"), ends_with(\"kůň\", \"ů\"),
+    find(\"žluťoučký\", \"ou\"), find(\"kůň\", \"x\"),
+    replace(\"a-b-c\", \"-\", \"+\"), repeat(\"ab\", 3),
+    parse_int(\"-42\"), parse_int(\"+100000000000000000000\"), parse_int(\"4x\"),
+    parse_float(\"2.5\"), parse_float(\"\"),
+];
+            ",
+            "x"
+        )
+        .map(|x| x.to_string()),
+        Some(
+            "[4, \"ť\", \"Příliš žluťoučký kůň\", \"KŮŇ\", \"úpěl\", \
+            [\"Příliš\", \"žluťoučký\", \"kůň\"], [\"a\", \"b\", \"\", \"c\"], \"a-b-c\", [\"k\", \"ů\", \"ň\"], \
+            true, false, some(4), none, \
+            \"a+b+c\", \"ababab\", \
+            some(-42), some(100000000000000000000), none, \
+            some(2.5), none]"
+                .to_string()
+        )
+    );
+    let mut interp = Interpreter::new(get_builtins(), Capabilities::ALL);
+    assert_eq!(
+        run(&mut interp, "\"kůň\"[3];", false),
+        Err(vec![Error {
+            msg: ErrorType::IndexOutOfRange(3, 3),
+            lines: vec![Location { start: 0, end: 7 }]
+        }])
+    );
+}
+
+#[test]
+fn split_empty_separator() {
+    let mut interp = Interpreter::new(get_builtins(), Capabilities::ALL);
+    assert_eq!(
+        run(&mut interp, "split(\"a\", \"\");", false),
+        Err(vec![Error {
+            msg: ErrorType::NativeFunctionError("Separator cannot be empty".to_string()),
+            lines: vec![Location { start: 0, end: 13 }]
+        }])
+    );
+}
+
+#[test]
+fn join_non_string() {
+    let mut interp = Interpreter::new(get_builtins(), Capabilities::ALL);
+    assert_eq!(
+        run(&mut interp, "join([1], \"\");", false),
+        Err(vec![Error {
+            msg: ErrorType::NativeFunctionError("Expected a string, got: 1".to_string()),
+            lines: vec![Location { start: 0, end: 12 }]
+        }])
+    );
+}
+
+#[test]
+fn repeat_negative() {
+    let mut interp = Interpreter::new(get_builtins(), Capabilities::ALL);
+    assert_eq!(
+        run(&mut interp, "repeat(\"ab\", -1);", false),
+        Err(vec![Error {
+            msg: ErrorType::NativeFunctionError("Cannot repeat a string a negative number of times: -1".to_string()),
+            lines: vec![Location { start: 0, end: 15 }]
+        }])
+    );
+}
+
+#[test]
+fn repeat_too_long() {
+    let mut interp = Interpreter::new(get_builtins(), Capabilities::ALL);
+    assert_eq!(
+        run(&mut interp, "repeat(\"ab\", 9223372036854775807);", false),
+        Err(vec![Error {
+            msg: ErrorType::NativeFunctionError("Repeated string would be too long: 9223372036854775807 times".to_string()),
+            lines: vec![Location { start: 0, end: 32 }]
+        }])
+    );
+}
+
+#[test]
+fn repeat_out_of_memory() {
+    let mut interp = Interpreter::new(get_builtins(), Capabilities::ALL);
+    assert_eq!(
+        run(&mut interp, "repeat(\"ab\", 1000000000000);", false),
+        Err(vec![Error {
+            msg: ErrorType::NativeFunctionError("Repeated string would be too long: 1000000000000 times".to_string()),
+            lines: vec![Location { start: 0, end: 26 }]
+        }])
+    );
+}
+
+#[test]
+fn parse_float_special_values() {
+    assert_eq!(
+        run_code(
+            "let x = [parse_float(\"nan\"), parse_float(\"inf\"), parse_float(\"-Infinity\"), parse_float(\"1e3\")];",
+            "x"
+        )
+        .map(|x| x.to_string()),
+        Some("[none, none, none, some(1000.0)]".to_string())
+    );
+}
+
+#[test]
+fn math_functions() {
+    assert_eq!(
+        run_code(
+            "
+let x = [
+    sqrt(16), sqrt(2.25), pow(2, 10), pow(2, 100), pow(-3, 3), pow(2.0, 0.5), pow(4, 0.5),
+    abs(-5), abs(-9223372036854775807 - 1), abs(-1.5),
+    min(3, 1, 2), max([1, 5, 2]), min(\"b\", \"a\"),
+    gcd(12, -18), gcd(0, 0), divmod(7, 2), divmod(-7, 2),
+    round(sin(pi / 2.0)), cos(0), exp(0), ln(e), log10(1000), atan2(1, 1) * 4.0 == pi, tan(0.0),
+];
+            ",
+            "x"
+        )
+        .map(|x| x.to_string()),
+        Some(
+            "[4.0, 1.5, 1024, 1267650600228229401496703205376, -27, 1.4142135623730951, 2.0, \
+            5, 9223372036854775808, 1.5, \
+            1, 5, \"a\", \
+            6, 0, [3, 1], [-3, -1], \
+            1, 1.0, 1.0, 1.0, 3.0, true, 0.0]"
+                .to_string()
+        )
+    );
+}
+
+#[test]
+fn pow_negative_exponent() {
+    let mut interp = Interpreter::new(get_builtins(), Capabilities::ALL);
+    assert_eq!(
+        run(&mut interp, "pow(2, -1);", false),
+        Err(vec![Error {
+            msg: ErrorType::NativeFunctionError("Cannot raise an integer to a negative power: -1".to_string()),
+            lines: vec![Location { start: 0, end: 9 }]
+        }])
+    );
+}
+
+#[test]
+fn pow_too_big() {
+    let mut interp = Interpreter::new(get_builtins(), Capabilities::ALL);
+    assert_eq!(
+        run(&mut interp, "pow(2, 100000000);", false),
+        Err(vec![Error {
+            msg: ErrorType::NativeFunctionError("Exponent is too big: 100000000".to_string()),
+            lines: vec![Location { start: 0, end: 16 }]
+        }])
+    );
+}
+
+#[test]
+fn sqrt_of_string() {
+    let mut interp = Interpreter::new(get_builtins(), Capabilities::ALL);
+    assert_eq!(
+        run(&mut interp, "sqrt(\"4\");", false),
+        Err(vec![Error {
+            msg: ErrorType::NativeFunctionError("Expected a number, got: \"4\"".to_string()),
+            lines: vec![Location { start: 0, end: 8 }]
+        }])
+    );
+}
+
+#[test]
+fn min_of_nothing() {
+    let mut interp = Interpreter::new(get_builtins(), Capabilities::ALL);
+    assert_eq!(
+        run(&mut interp, "min([]);", false),
+        Err(vec![Error {
+            msg: ErrorType::NativeFunctionError("Expected at least one value".to_string()),
+            lines: vec![Location { start: 0, end: 6 }]
+        }])
+    );
+}
+
+#[test]
+fn divmod_by_zero() {
+    let mut interp = Interpreter::new(get_builtins(), Capabilities::ALL);
+    assert_eq!(
+        run(&mut interp, "divmod(1, 0);", false),
+        Err(vec![Error {
+            msg: ErrorType::NativeFunctionError("Attempted division by zero".to_string()),
+            lines: vec![Location { start: 0, end: 11 }]
+        }])
+    );
+}
+
+#[test]
+fn gcd_of_float() {
+    let mut interp = Interpreter::new(get_builtins(), Capabilities::ALL);
+    assert_eq!(
+        run(&mut interp, "gcd(1.0, 2);", false),
+        Err(vec![Error {
+            msg: ErrorType::NativeFunctionError("Expected an integer, got: 1.0".to_string()),
+            lines: vec![Location { start: 0, end: 10 }]
+        }])
+    );
+}
+
+#[test]
+fn sqrt_of_negative() {
+    let mut interp = Interpreter::new(get_builtins(), Capabilities::ALL);
+    assert_eq!(
+        run(&mut interp, "sqrt(-1);", false),
+        Err(vec![Error {
+            msg: ErrorType::NativeFunctionError("Cannot take the square root of a negative number: -1".to_string()),
+            lines: vec![Location { start: 0, end: 7 }]
+        }])
+    );
+}
+
+#[test]
+fn ln_of_zero() {
+    let mut interp = Interpreter::new(get_builtins(), Capabilities::ALL);
+    assert_eq!(
+        run(&mut interp, "ln(0);", false),
+        Err(vec![Error {
+            msg: ErrorType::NativeFunctionError("Cannot take the logarithm of a number which is not positive: 0".to_string()),
+            lines: vec![Location { start: 0, end: 4 }]
+        }])
+    );
+}
+
+#[test]
+fn log10_of_negative() {
+    let mut interp = Interpreter::new(get_builtins(), Capabilities::ALL);
+    assert_eq!(
+        run(&mut interp, "log10(-2.5);", false),
+        Err(vec![Error {
+            msg: ErrorType::NativeFunctionError("Cannot take the logarithm of a number which is not positive: -2.5".to_string()),
+            lines: vec![Location { start: 0, end: 10 }]
+        }])
+    );
+}
+
+#[test]
+fn max_incomparable() {
+    let mut interp = Interpreter::new(get_builtins(), Capabilities::ALL);
+    assert_eq!(
+        run(&mut interp, "max(1, 1.5);", false),
+        Err(vec![Error {
+            msg: ErrorType::NativeFunctionError("Invalid values: \"1\" and \"1.5\"".to_string()),
+            lines: vec![Location { start: 0, end: 10 }]
+        }])
+    );
+}
+
+#[test]
+fn math_special_values() {
+    // the functions defined for every number follow IEEE 754 for NaN and infinities
+    let code = "
+let inf = 1.0 / 0.0;
+let nan = 0.0 / 0.0;
+let x = [exp(1000.0), sin(inf), sqrt(inf), sqrt(nan), ln(inf), ln(nan), sqrt(0), pow(-8.0, 0.5)];
+    ";
+    assert_eq!(
+        run_code(code, "x").map(|x| x.to_string()),
+        Some("[inf, NaN, inf, NaN, inf, NaN, 0.0, NaN]".to_string())
+    );
+    // min and max compare with \"<\", so overloading it is enough
+    let code = "
+struct Money { cents }
+impl Money {
+    fun <(self, other) {
+        return self.cents < other.cents;
+    }
+}
+let x = [min(Money(5), Money(2), Money(9)).cents, max([Money(5), Money(2), Money(9)]).cents];
+    ";
+    assert_eq!(run_code(code, "x").map(|x| x.to_string()), Some("[2, 9]".to_string()));
+}
+
+#[test]
+fn file_functions() {
+    let dir = std::env::temp_dir().join(format!("moth_file_functions_{}", std::process::id()));
+    let dir = dir.to_str().unwrap();
+    let code = format!(
+        "
+let dir = \"{dir}\";
+mkdir(dir + \"/sub\");
+write_file(dir + \"/a.txt\", \"one\\ntwo\");
+append_file(dir + \"/a.txt\", \"\\nthree\");
+let text = read_file(dir + \"/a.txt\");
+let lines = read_lines(dir + \"/a.txt\");
+let before = list_dir(dir);
+remove_file(dir + \"/a.txt\");
+remove_file(dir + \"/sub\");
+let x = [text == \"one\\ntwo\\nthree\", lines, before, exists(dir + \"/a.txt\"), list_dir(dir)];
+        "
+    );
+    let res = run_code(&code, "x").map(|x| x.to_string());
+    std::fs::remove_dir(dir).unwrap();
+    assert_eq!(
+        res,
+        Some("[true, [\"one\", \"two\", \"three\"], [\"a.txt\", \"sub\"], false, []]".to_string())
+    );
+
+    let mut interp = Interpreter::new(get_builtins(), Capabilities::ALL);
+    assert_eq!(
+        run(&mut interp, "read_file(\"/nonexistent\");", false),
+        Err(vec![Error {
+            msg: ErrorType::NativeFunctionError(
+                "Cannot read \"/nonexistent\": No such file or directory (os error 2)".to_string()
+            ),
+            lines: vec![Location { start: 0, end: 24 }]
+        }])
+    );
+    // embedders can turn it off
+    let mut interp = Interpreter::new(get_builtins(), Capabilities::NONE);
+    assert_eq!(
+        run(&mut interp, "exists(\"/\");", false),
+        Err(vec![Error {
+            msg: ErrorType::NativeFunctionError("File access is disabled".to_string()),
+            lines: vec![Location { start: 0, end: 10 }]
+        }])
+    );
+}
+
+#[test]
+fn reading_input() {
+    let mut interp = Interpreter::new(get_builtins(), Capabilities::ALL);
+    interp.set_input(Box::new(std::io::Cursor::new("first\r\n\nthird\nrest\nof it")));
+    let code = "
+let x = [read_line(), input(), input(\"\"), read_all(), read_line(), read_all()];
+    ";
+    run(&mut interp, code, false).unwrap();
+    assert_eq!(
+        interp.get_val("x".to_string()).map(|x| x.to_string()),
+        Some("[some(\"first\"), some(\"\"), some(\"third\"), \"rest\nof it\", none, \"\"]".to_string())
+    );
+}
+
+#[test]
+fn args_env_exit() {
+    let mut interp = Interpreter::new(get_builtins(), Capabilities::ALL);
+    interp.set_args(vec!["a".to_string(), "b c".to_string()]);
+    let code = "
+set_env(\"MOTH_ARGS_ENV_EXIT\", \"1\");
+let x = [args(), env(\"MOTH_ARGS_ENV_EXIT\"), env(\"MOTH_ARGS_ENV_EXIT_MISSING\")];
+fun stop() { exit(3); }
+stop();
+let y = 1;
+    ";
+    // exit unwinds all the way out, skipping the rest of the program
+    assert_eq!(
+        run(&mut interp, code, false),
+        Err(vec![Error {
+            msg: ErrorType::Exit(3),
+            lines: vec![Location { start: 130, end: 136 }]
+        }])
+    );
+    assert_eq!(
+        interp.get_val("x".to_string()).map(|x| x.to_string()),
+        Some("[[\"a\", \"b c\"], some(\"1\"), none]".to_string())
+    );
+    assert_eq!(interp.get_val("y".to_string()), None);
+}
+
+#[test]
+fn exit_code_out_of_range() {
+    let mut interp = Interpreter::new(get_builtins(), Capabilities::ALL);
+    assert_eq!(
+        run(&mut interp, "exit(10000000000);", false),
+        Err(vec![Error {
+            msg: ErrorType::NativeFunctionError("Exit code out of range: 10000000000".to_string()),
+            lines: vec![Location { start: 0, end: 16 }]
+        }])
+    );
+}
+
+#[test]
+fn env_invalid_name() {
+    let mut interp = Interpreter::new(get_builtins(), Capabilities::ALL);
+    assert_eq!(
+        run(&mut interp, "env(\"A=B\");", false),
+        Err(vec![Error {
+            msg: ErrorType::NativeFunctionError("Invalid environment variable name: \"A=B\"".to_string()),
+            lines: vec![Location { start: 0, end: 9 }]
+        }])
+    );
+}
+
+#[test]
+fn random_numbers() {
+    let code = "
+seed(42);
+let ls = [1, 2, 3, 4, 5, 6, 7, 8];
+shuffle(ls);
+let x = [random(), random_int(1, 6), random_int(-9223372036854775807 - 1, 9223372036854775807), choice(ls), ls];
+let shuffled = [] + ls;
+sort(shuffled);
+let floats = map([1, 2, 3, 4, 5, 6, 7, 8, 9, 10], |_| random());
+let ints = map([1, 2, 3, 4, 5, 6, 7, 8, 9, 10], |_| random_int(-2, 2));
+let y = [
+    shuffled, all(floats, |f| (f >= 0.0) && (f < 1.0)), all(ints, |n| (n >= -2) && (n <= 2)), random_int(3, 3),
+];
+    ";
+    // the same seed gives the same values
+    let first = run_code(code, "x").unwrap();
+    assert_eq!(run_code(code, "x"), Some(first));
+    assert_eq!(
+        run_code(code, "y").map(|y| y.to_string()),
+        Some("[[1, 2, 3, 4, 5, 6, 7, 8], true, true, 3]".to_string())
+    );
+}
+
+#[test]
+fn random_int_empty_range() {
+    let mut interp = Interpreter::new(get_builtins(), Capabilities::ALL);
+    assert_eq!(
+        run(&mut interp, "random_int(2, 1);", false),
+        Err(vec![Error {
+            msg: ErrorType::NativeFunctionError("Empty range: 2 to 1".to_string()),
+            lines: vec![Location { start: 0, end: 15 }]
+        }])
+    );
+}
+
+#[test]
+fn choice_from_empty_list() {
+    let mut interp = Interpreter::new(get_builtins(), Capabilities::ALL);
+    assert_eq!(
+        run(&mut interp, "choice([]);", false),
+        Err(vec![Error {
+            msg: ErrorType::NativeFunctionError("Cannot choose from an empty list".to_string()),
+            lines: vec![Location { start: 0, end: 9 }]
+        }])
+    );
+}
+
+/// runs the code with the text as its input, so that JSON does not have to be escaped in moth strings too
+fn run_with_input(code: &str, input: &str, val: &str) -> Result<ValueType, Vec<Error>> {
+    let mut interp = Interpreter::new(get_builtins(), Capabilities::ALL);
+    interp.set_input(Box::new(std::io::Cursor::new(input.to_string())));
+    run(&mut interp, code, false)?;
+    Ok(interp.get_val(val.to_string()).unwrap())
+}
+
+fn json_round_trip(input: &str) -> Result<String, Vec<Error>> {
+    run_with_input("let x = json_stringify(json_parse(read_all()));", input, "x").map(|x| match x {
+        ValueType::String(s) => s,
+        _ => unreachable!(),
+    })
+}
+
+#[test]
+fn json_values() {
+    let cases = [
+        ("null", "null"),
+        (" true ", "true"),
+        ("false", "false"),
+        ("0", "0"),
+        ("-12", "-12"),
+        ("123456789012345678901234567890", "123456789012345678901234567890"),
+        ("1.5", "1.5"),
+        ("-0.25e2", "-25.0"),
+        ("1E3", "1000.0"),
+        ("[]", "[]"),
+        ("{}", "{}"),
+        ("[1, [2, []], {\"a\": null}]", "[1,[2,[]],{\"a\":null}]"),
+        // keys are sorted, the later duplicate wins
+        ("{\"b\": 1, \"a\": 2, \"b\": 3}", "{\"a\":2,\"b\":3}"),
+        ("\n\t{ \"k\" :\r\n[ ] }\n", "{\"k\":[]}"),
+    ];
+    for (input, output) in cases {
+        assert_eq!(json_round_trip(input), Ok(output.to_string()), "{input}");
+    }
+    // objects are instances, so their fields can be used directly
+    assert_eq!(
+        run_with_input(
+            "let obj = json_parse(read_all()); let x = [obj.name, obj.tags[1], obj.nested.ok];",
+            "{\"name\": \"moth\", \"tags\": [\"a\", \"b\"], \"nested\": {\"ok\": true}}",
+            "x"
+        )
+        .map(|x| x.to_string()),
+        Ok("[\"moth\", \"b\", true]".to_string())
+    );
+}
+
+#[test]
+fn json_strings() {
+    let cases = [
+        (r#""plain""#, "plain"),
+        (r#""\" \\ \/ \b \f \n \r \t""#, "\" \\ / \u{8} \u{c} \n \r \t"),
+        (r#""\u0041\u00e9\u017e""#, "Aéž"),
+        (r#""žluťoučký kůň 日本""#, "žluťoučký kůň 日本"),
+        // outside of the basic plane it is a surrogate pair
+        (r#""\ud83d\ude00""#, "😀"),
+        (r#""😀""#, "😀"),
+        (r#""""#, ""),
+    ];
+    for (input, output) in cases {
+        assert_eq!(
+            run_with_input("let x = json_parse(read_all());", input, "x"),
+            Ok(ValueType::String(output.to_string())),
+            "{input}"
+        );
+    }
+    // only what has to be escaped is, the rest is left as it is
+    assert_eq!(
+        json_round_trip(r#""a\"b\\c\/d\n\r\t\u0001\u001f ž 😀""#),
+        Ok(r#""a\"b\\c/d\n\r\t\u0001\u001f ž 😀""#.to_string())
+    );
+}
+
+#[test]
+fn json_stringify_values() {
+    let code = "
+struct Point { x, y }
+impl Point {
+    fun len(self) { return self.x + self.y; }
+}
+let p = Point(1, some(2));
+let x = [
+    json_stringify(p),
+    json_stringify([(), none, 1.0, -3, \"é\", [p]], 2),
+    json_stringify([], 2),
+];
+    ";
+    assert_eq!(
+        run_code(code, "x").map(|x| x.to_string()),
+        Some(
+            "[\"{\"x\":1,\"y\":2}\", \"[\n  null,\n  null,\n  1.0,\n  -3,\n  \"é\",\n  [\n    {\n      \"x\": 1,\n      \"y\": 2\n    }\n  ]\n]\", \"[]\"]"
+                .to_string()
+        )
+    );
+}
+
+#[test]
+fn json_stringify_shadowed_names() {
+    let code = "
+struct Point { x, y }
+impl Point {
+    fun len(self) { return self.x + self.y; }
+}
+struct Object { len }
+impl Object {
+    fun x(self) { return 1; }
+}
+let p = Point(1, 2);
+let o = json_parse(\"{\\\"x\\\": 3, \\\"len\\\": 4}\");
+fun stringify() {
+    let Point = Object(5);
+    return [json_stringify(p), json_stringify(o), json_stringify(Point)];
+}
+let x = stringify();
+    ";
+    assert_eq!(
+        run_code(code, "x").map(|x| x.to_string()),
+        Some("[\"{\"x\":1,\"y\":2}\", \"{\"len\":4,\"x\":3}\", \"{\"len\":5}\"]".to_string())
+    );
+}
+
+#[test]
+fn json_errors() {
+    let parse_errs = [
+        ("", "Invalid JSON at character 0: unexpected end of input"),
+        ("[1, 2", "Invalid JSON at character 5: expected \",\" or \"]\""),
+        ("[1,]", "Invalid JSON at character 3: expected a value"),
+        ("{\"a\" 1}", "Invalid JSON at character 5: expected \":\""),
+        ("{1: 2}", "Invalid JSON at character 1: expected \"\"\""),
+        ("01", "Invalid JSON at character 1: expected the end of input"),
+        ("1.", "Invalid JSON at character 2: expected a digit"),
+        ("nul", "Invalid JSON at character 0: expected a value"),
+        ("\"abc", "Invalid JSON at character 4: unterminated string"),
+        ("\"\\x\"", "Invalid JSON at character 1: invalid escape \"\\x\""),
+        ("\"\\u12g4\"", "Invalid JSON at character 5: expected a hexadecimal digit"),
+        ("\"\\ud83d\"", "Invalid JSON at character 3: unpaired surrogate"),
+        ("\"\\ude00\"", "Invalid JSON at character 3: unpaired surrogate"),
+        ("\"a\nb\"", "Invalid JSON at character 2: control characters have to be escaped"),
+        ("1 2", "Invalid JSON at character 2: expected the end of input"),
+    ];
+    for (input, msg) in parse_errs {
+        assert_eq!(
+            run_with_input("let x = json_parse(read_all());", input, "x"),
+            Err(vec![Error {
+                msg: ErrorType::NativeFunctionError(msg.to_string()),
+                lines: vec![Location { start: 8, end: 29 }]
+            }]),
+            "{input}"
+        );
+    }
+    let deep = "[".repeat(10000);
+    assert_eq!(
+        run_with_input("let x = json_parse(read_all());", &deep, "x"),
+        Err(vec![Error {
+            msg: ErrorType::NativeFunctionError("Invalid JSON at character 512: nested too deep".to_string()),
+            lines: vec![Location { start: 8, end: 29 }]
+        }])
+    );
+}
+
+#[test]
+fn json_stringify_function() {
+    let mut interp = Interpreter::new(get_builtins(), Capabilities::ALL);
+    assert_eq!(
+        run(&mut interp, "json_stringify(|x| x);", false),
+        Err(vec![Error {
+            msg: ErrorType::NativeFunctionError("Cannot convert a function to JSON".to_string()),
+            lines: vec![Location { start: 0, end: 20 }]
+        }])
+    );
+}
+
+#[test]
+fn json_stringify_native_function() {
+    let mut interp = Interpreter::new(get_builtins(), Capabilities::ALL);
+    assert_eq!(
+        run(&mut interp, "json_stringify([print]);", false),
+        Err(vec![Error {
+            msg: ErrorType::NativeFunctionError("Cannot convert a function to JSON".to_string()),
+            lines: vec![Location { start: 0, end: 22 }]
+        }])
+    );
+}
+
+#[test]
+fn json_stringify_cycle() {
+    let mut interp = Interpreter::new(get_builtins(), Capabilities::ALL);
+    assert_eq!(
+        run(&mut interp, "let ls = [1]; push(ls, ls); json_stringify(ls);", false),
+        Err(vec![Error {
+            msg: ErrorType::NativeFunctionError("Cannot convert a value containing itself to JSON".to_string()),
+            lines: vec![Location { start: 28, end: 45 }]
+        }])
+    );
+}
+
+#[test]
+fn json_stringify_inf() {
+    let mut interp = Interpreter::new(get_builtins(), Capabilities::ALL);
+    assert_eq!(
+        run(&mut interp, "json_stringify(1.0 / 0.0);", false),
+        Err(vec![Error {
+            msg: ErrorType::NativeFunctionError("Cannot convert inf to JSON".to_string()),
+            lines: vec![Location { start: 0, end: 24 }]
+        }])
+    );
+}
+
+#[test]
+fn json_stringify_negative_indent() {
+    let mut interp = Interpreter::new(get_builtins(), Capabilities::ALL);
+    assert_eq!(
+        run(&mut interp, "json_stringify(1, -1);", false),
+        Err(vec![Error {
+            msg: ErrorType::NativeFunctionError("Indent cannot be negative: -1".to_string()),
+            lines: vec![Location { start: 0, end: 20 }]
+        }])
+    );
+}
+
+#[test]
+fn json_stringify_huge_indent() {
+    let mut interp = Interpreter::new(get_builtins(), Capabilities::ALL);
+    assert_eq!(
+        run(&mut interp, "json_stringify([1], 9223372036854775807);", false),
+        Err(vec![Error {
+            msg: ErrorType::NativeFunctionError("Indent cannot be bigger than 64: 9223372036854775807".to_string()),
+            lines: vec![Location { start: 0, end: 39 }]
+        }])
+    );
+}
+
+#[test]
+fn time_functions() {
+    let dates = [
+        (0i64, "1970-01-01 00:00:00 001 Thu Jan"),
+        (-1, "1969-12-31 23:59:59 365 Wed Dec"),
+        (951782400, "2000-02-29 00:00:00 060 Tue Feb"),
+        (978220800, "2000-12-31 00:00:00 366 Sun Dec"),
+        (1700000000, "2023-11-14 22:13:20 318 Tue Nov"),
+        (-62135596800, "0001-01-01 00:00:00 001 Mon Jan"),
+        (253402300799, "9999-12-31 23:59:59 365 Fri Dec"),
+    ];
+    for (epoch, date) in dates {
+        assert_eq!(
+            run_code(
+                &format!("let x = format_time({epoch}, \"%Y-%m-%d %H:%M:%S %j %a %b\");"),
+                "x"
+            ),
+            Some(ValueType::String(date.to_string()))
+        );
+    }
+    let code = "
+let x = [format_time(86399), format_time(0, \"100%%\")];
+let ms = now_ms();
+let ns = now_ns();
+let start = clock();
+sleep(5);
+let y = [ms > 1700000000000, ns / 1000000 >= ms, clock() - start >= 0.005, (time() == ms / 1000) || (time() == ms / 1000 + 1)];
+    ";
+    assert_eq!(
+        run_code(code, "x").map(|x| x.to_string()),
+        Some("[\"1970-01-01 23:59:59\", \"100%\"]".to_string())
+    );
+    assert_eq!(
+        run_code(code, "y").map(|y| y.to_string()),
+        Some("[true, true, true, true]".to_string())
+    );
+}
+
+#[test]
+fn format_time_unknown_specifier() {
+    let mut interp = Interpreter::new(get_builtins(), Capabilities::ALL);
+    assert_eq!(
+        run(&mut interp, "format_time(0, \"%Q\");", false),
+        Err(vec![Error {
+            msg: ErrorType::NativeFunctionError("Unknown format specifier: %Q".to_string()),
+            lines: vec![Location { start: 0, end: 19 }]
+        }])
+    );
+}
+
+#[test]
+fn sleep_negative() {
+    let mut interp = Interpreter::new(get_builtins(), Capabilities::ALL);
+    assert_eq!(
+        run(&mut interp, "sleep(-1);", false),
+        Err(vec![Error {
+            msg: ErrorType::NativeFunctionError("Cannot sleep for a negative time: -1".to_string()),
+            lines: vec![Location { start: 0, end: 8 }]
+        }])
+    );
+}
+
+/// runs the code with an extra native function
+/// varcheck does not know about it, so it is skipped
+fn run_with_native(code: &str, name: &str, func: NativeFunction) -> (Interpreter, Result<(), Error>) {
+    let mut builtins = get_builtins();
+    builtins.insert(name.to_string(), ValueType::NativeFunction(func));
+    let mut interp = Interpreter::new(builtins, Capabilities::ALL);
+    let tokens = lexer::lex(code).unwrap();
+    let ast = parser::parse(tokens).unwrap();
+    let ast = reassociate(operator_precedences(), ast).unwrap();
+    let res = simplify(ast).and_then(|ast| interp.interpret(ast));
+    (interp, res)
+}
+
+#[test]
+fn native_call_back() {
+    let apply: NativeFunction = |interp, args| {
+        let [f, x] = &args[..] else {
+            return Err(format!("Function takes exactly 2 arguments, got: {}", args.len()));
+        };
+        interp.call_back(f.clone(), vec![x.clone()])
+    };
+    let (interp, res) = run_with_native("let x = apply(|n| n * 2, 21);", "apply", apply);
+    assert_eq!(res, Ok(()));
+    assert_eq!(interp.get_val("x".to_string()), Some(ValueType::Int(42)));
+
+    // the error is reported inside the function, not at the native
+    let (_, res) = run_with_native("let x = apply(|n| n / 0, 1);", "apply", apply);
+    assert_eq!(
+        res,
+        Err(Error {
+            msg: ErrorType::NativeFunctionError("Attempted division by zero".to_string()),
+            lines: vec![Location { start: 20, end: 20 }]
+        })
+    );
+    // the call itself is at the native
+    let (_, res) = run_with_native("let x = apply(|| 1, 1);", "apply", apply);
+    assert_eq!(
+        res,
+        Err(Error {
+            msg: ErrorType::IncorrectParameterCount(1, 0),
+            lines: vec![Location { start: 8, end: 21 }]
+        })
+    );
+}
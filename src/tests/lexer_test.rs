@@ -23,6 +23,15 @@ fn lex_number() {
     assert_eq!(t[0].val, TokenType::Int(10))
 }
 
+#[test]
+fn lex_big_number() {
+    let t = lex("9223372036854775807").unwrap();
+    assert_eq!(t[0].val, TokenType::Int(i64::MAX));
+    let t = lex("9223372036854775808").unwrap();
+    assert_eq!(t[0].val.to_string(), "9223372036854775808");
+    assert!(matches!(t[0].val, TokenType::BigInt(_)));
+}
+
 #[test]
 fn lex_float() {
     let nums = [("1.1", 1.1), ("10.1", 10.1)];
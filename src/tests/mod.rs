@@ -1,3 +1,5 @@
+#[allow(clippy::bool_assert_comparison)]
 mod interpreter_test;
 mod lexer_test;
+#[allow(clippy::needless_borrow, clippy::useless_conversion)]
 mod parser_test;
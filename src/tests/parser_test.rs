@@ -913,3 +913,328 @@ fn lambda_params() {
         }])
     )
 }
+
+#[test]
+fn test_varcheck_safe_access() {
+    let input = "struct P { x } let y = P(1)?.x; print(y);".to_string();
+    let tokens = lex(&input).unwrap();
+    let ast = parse(tokens).unwrap();
+    let builtins = get_builtins()
+        .keys()
+        .map(|name| (name.clone(), (Location { start: 0, end: 0 }, false)))
+        .collect::<HashMap<_, _>>();
+    let checked = varcheck::varcheck(builtins, &ast);
+    assert_eq!(
+        checked,
+        Err((
+            vec![Error {
+                msg: ErrorType::UnnecessarySafeAccess,
+                lines: vec![Location { start: 23, end: 26 }]
+            }],
+            vec![]
+        ))
+    );
+}
+
+#[test]
+fn test_varcheck_missing_trait_method() {
+    let input = "trait T { fun a(self); fun b(self) {} } struct S {} impl T for S {}".to_string();
+    let tokens = lex(&input).unwrap();
+    let ast = parse(tokens).unwrap();
+    let builtins = get_builtins()
+        .keys()
+        .map(|name| (name.clone(), (Location { start: 0, end: 0 }, false)))
+        .collect::<HashMap<_, _>>();
+    let checked = varcheck::varcheck(builtins, &ast);
+    assert_eq!(
+        checked.map_err(|(_, errs)| errs),
+        Err(vec![Error {
+            msg: ErrorType::MissingTraitMethod("T".to_string(), "a".to_string()),
+            lines: vec![Location { start: 63, end: 63 }, Location { start: 14, end: 14 }]
+        }])
+    );
+}
+
+#[test]
+fn test_varcheck_method_needs_instance() {
+    let input = "struct S {} impl S { fun a(self) {} fun b() {} } S.b(); S.a();".to_string();
+    let tokens = lex(&input).unwrap();
+    let ast = parse(tokens).unwrap();
+    let builtins = get_builtins()
+        .keys()
+        .map(|name| (name.clone(), (Location { start: 0, end: 0 }, false)))
+        .collect::<HashMap<_, _>>();
+    let checked = varcheck::varcheck(builtins, &ast);
+    assert_eq!(
+        checked.map_err(|(_, errs)| errs),
+        Err(vec![Error {
+            msg: ErrorType::MethodNeedsInstance("a".to_string(), "S".to_string()),
+            lines: vec![Location { start: 56, end: 56 }, Location { start: 58, end: 58 }]
+        }])
+    );
+}
+
+#[test]
+fn test_varcheck_method_needs_instance_parens() {
+    let input = "struct S {} impl S { fun a(self) {} } (S).a();".to_string();
+    let tokens = lex(&input).unwrap();
+    let ast = parse(tokens).unwrap();
+    let builtins = get_builtins()
+        .keys()
+        .map(|name| (name.clone(), (Location { start: 0, end: 0 }, false)))
+        .collect::<HashMap<_, _>>();
+    let checked = varcheck::varcheck(builtins, &ast);
+    assert_eq!(
+        checked.map_err(|(_, errs)| errs),
+        Err(vec![Error {
+            msg: ErrorType::MethodNeedsInstance("a".to_string(), "S".to_string()),
+            lines: vec![Location { start: 38, end: 40 }, Location { start: 42, end: 42 }]
+        }])
+    );
+}
+
+#[test]
+fn struct_literal() {
+    let src = parse(lex("P { x: 1, ..p };").unwrap());
+    assert_eq!(
+        src,
+        Ok(vec![LStmt {
+            val: Stmt::Expr(LExpr {
+                val: Expr::StructLiteral(
+                    Identifier {
+                        val: "P".to_string(),
+                        loc: Location { start: 0, end: 0 }
+                    },
+                    vec![(
+                        Identifier {
+                            val: "x".to_string(),
+                            loc: Location { start: 4, end: 4 }
+                        },
+                        LExpr {
+                            val: Expr::Int(1),
+                            loc: Location { start: 7, end: 7 }
+                        }
+                    )],
+                    Some(
+                        LExpr {
+                            val: Expr::Identifier("p".to_string()),
+                            loc: Location { start: 12, end: 12 }
+                        }
+                        .into()
+                    )
+                ),
+                loc: Location { start: 0, end: 14 },
+            }),
+            loc: Location { start: 0, end: 14 },
+        }])
+    )
+}
+
+#[test]
+fn struct_literal_in_condition() {
+    let src = parse(lex("while x {}").unwrap());
+    assert_eq!(
+        src,
+        Ok(vec![LStmt {
+            val: Stmt::While(
+                LExpr {
+                    val: Expr::Identifier("x".to_string()),
+                    loc: Location { start: 6, end: 6 }
+                },
+                vec![],
+                None
+            ),
+            loc: Location { start: 0, end: 9 },
+        }])
+    )
+}
+
+#[test]
+fn test_varcheck_struct_literal_fields() {
+    let input = "struct P { x, y, z = 0 } let p = P { x: 1, w: 2 }; print(p);".to_string();
+    let tokens = lex(&input).unwrap();
+    let ast = parse(tokens).unwrap();
+    let builtins = get_builtins()
+        .keys()
+        .map(|name| (name.clone(), (Location { start: 0, end: 0 }, false)))
+        .collect::<HashMap<_, _>>();
+    let checked = varcheck::varcheck(builtins, &ast);
+    assert_eq!(
+        checked.map_err(|(_, errs)| errs),
+        Err(vec![
+            Error {
+                msg: ErrorType::UnknownField("w".to_string()),
+                lines: vec![Location { start: 43, end: 43 }]
+            },
+            Error {
+                msg: ErrorType::MissingField("y".to_string(), "P".to_string()),
+                lines: vec![Location { start: 33, end: 33 }, Location { start: 14, end: 14 }]
+            },
+        ])
+    );
+}
+
+#[test]
+fn test_varcheck_yield_outside_generator() {
+    let input = "fun f() { yield 1; } fun* g() { yield 1; } f(); g();".to_string();
+    let tokens = lex(&input).unwrap();
+    let ast = parse(tokens).unwrap();
+    let builtins = get_builtins()
+        .keys()
+        .map(|name| (name.clone(), (Location { start: 0, end: 0 }, false)))
+        .collect::<HashMap<_, _>>();
+    let checked = varcheck::varcheck(builtins, &ast);
+    assert_eq!(
+        checked.map_err(|(_, errs)| errs),
+        Err(vec![Error {
+            msg: ErrorType::YieldOutsideGenerator,
+            lines: vec![Location { start: 10, end: 16 }]
+        }])
+    );
+}
+
+#[test]
+fn labeled_loop() {
+    let src = parse(lex("'a: loop { break 'a 1; }").unwrap());
+    assert_eq!(
+        src,
+        Ok(vec![LStmt {
+            val: Stmt::Expr(LExpr {
+                val: Expr::Loop(
+                    vec![LStmt {
+                        val: Stmt::Break(
+                            Some(Identifier {
+                                val: "a".to_string(),
+                                loc: Location { start: 17, end: 18 }
+                            }),
+                            Some(LExpr {
+                                val: Expr::Int(1),
+                                loc: Location { start: 20, end: 20 }
+                            })
+                        ),
+                        loc: Location { start: 11, end: 20 },
+                    }],
+                    Some(Identifier {
+                        val: "a".to_string(),
+                        loc: Location { start: 0, end: 1 }
+                    })
+                ),
+                loc: Location { start: 0, end: 23 },
+            }),
+            loc: Location { start: 0, end: 23 },
+        }])
+    )
+}
+
+#[test]
+fn test_varcheck_labels() {
+    let input = "for x in [] { print(x); break 'a; } while 1 < 2 { break 1; } 'b: while true { loop { break 'b; } }".to_string();
+    let tokens = lex(&input).unwrap();
+    let ast = parse(tokens).unwrap();
+    let builtins = get_builtins()
+        .keys()
+        .map(|name| (name.clone(), (Location { start: 0, end: 0 }, false)))
+        .collect::<HashMap<_, _>>();
+    let checked = varcheck::varcheck(builtins, &ast);
+    assert_eq!(
+        checked.map_err(|(_, errs)| errs),
+        Err(vec![
+            Error {
+                msg: ErrorType::UnknownLabel("a".to_string()),
+                lines: vec![Location { start: 30, end: 31 }]
+            },
+            Error {
+                msg: ErrorType::BreakWithValue,
+                lines: vec![Location { start: 56, end: 56 }]
+            },
+        ])
+    );
+}
+
+#[test]
+fn test_varcheck_loop_never_ends() {
+    let input = "while true { print(1); } while true { if 1 < 2 { break; } } while true { loop { break; } }".to_string();
+    let tokens = lex(&input).unwrap();
+    let ast = parse(tokens).unwrap();
+    let builtins = get_builtins()
+        .keys()
+        .map(|name| (name.clone(), (Location { start: 0, end: 0 }, false)))
+        .collect::<HashMap<_, _>>();
+    let checked = varcheck::varcheck(builtins, &ast);
+    assert_eq!(
+        checked.map_err(|(warns, _)| warns),
+        Err(vec![
+            Error {
+                msg: ErrorType::LoopNeverEnds,
+                lines: vec![Location { start: 6, end: 9 }]
+            },
+            Error {
+                msg: ErrorType::LoopNeverEnds,
+                lines: vec![Location { start: 66, end: 69 }]
+            },
+        ])
+    );
+}
+
+#[test]
+fn test_varcheck_loop_keyword_never_ends() {
+    let input = "loop { print(1); } loop { break; } print(loop { print(2); });".to_string();
+    let tokens = lex(&input).unwrap();
+    let ast = parse(tokens).unwrap();
+    let builtins = get_builtins()
+        .keys()
+        .map(|name| (name.clone(), (Location { start: 0, end: 0 }, false)))
+        .collect::<HashMap<_, _>>();
+    let checked = varcheck::varcheck(builtins, &ast);
+    assert_eq!(
+        checked.map_err(|(warns, _)| warns),
+        Err(vec![
+            Error {
+                msg: ErrorType::LoopNeverEnds,
+                lines: vec![Location { start: 0, end: 17 }]
+            },
+            Error {
+                msg: ErrorType::LoopNeverEnds,
+                lines: vec![Location { start: 41, end: 58 }]
+            },
+        ])
+    );
+}
+
+#[test]
+fn test_varcheck_assign_constant() {
+    let input = "const A = 1; A = 2; { let A = 3; A = 4; }".to_string();
+    let tokens = lex(&input).unwrap();
+    let ast = parse(tokens).unwrap();
+    let builtins = get_builtins()
+        .keys()
+        .map(|name| (name.clone(), (Location { start: 0, end: 0 }, false)))
+        .collect::<HashMap<_, _>>();
+    let checked = varcheck::varcheck(builtins, &ast);
+    assert_eq!(
+        checked.map_err(|(_, errs)| errs),
+        Err(vec![Error {
+            msg: ErrorType::AssignToConstant("A".to_string()),
+            lines: vec![Location { start: 13, end: 17 }, Location { start: 6, end: 6 }]
+        }])
+    );
+}
+
+#[test]
+fn test_varcheck_nested_test() {
+    let input = "test \"a\" { assert true; } { test \"b\" {} }".to_string();
+    let tokens = lex(&input).unwrap();
+    let ast = parse(tokens).unwrap();
+    let builtins = get_builtins()
+        .keys()
+        .map(|name| (name.clone(), (Location { start: 0, end: 0 }, false)))
+        .collect::<HashMap<_, _>>();
+    let checked = varcheck::varcheck(builtins, &ast);
+    assert_eq!(
+        checked.map_err(|(_, errs)| errs),
+        Err(vec![Error {
+            msg: ErrorType::TestNotAtTopLevel("b".to_string()),
+            lines: vec![Location { start: 33, end: 35 }]
+        }])
+    );
+}
@@ -1,4 +1,4 @@
-use crate::{associativity::Precedence, error::Error, exprstmt::*, located::Location};
+use crate::{associativity::Precedence, bigint::BigInt, error::Error, exprstmt::*, located::Location};
 
 pub trait StmtVisitor<T> {
     fn visit_stmt(&mut self, stmt: LStmt) -> Result<T, Error> {
@@ -10,15 +10,23 @@ pub trait StmtVisitor<T> {
             Stmt::AssignIndex(ls, idx, val) => self.assignindex(loc, ls, idx, val),
             Stmt::Block(block) => self.block(loc, block),
             Stmt::If(blocks, els) => self.if_else(loc, blocks, els),
-            Stmt::While(cond, block) => self.whiles(loc, cond, block),
+            Stmt::While(cond, block, label) => self.whiles(loc, cond, block, label),
             Stmt::FunDecl(name, params, block) => self.fun(loc, name, params, block),
+            Stmt::GenFunDecl(name, params, block) => self.gen_fun(loc, name, params, block),
             Stmt::OperatorDecl(name, params, block, prec) => self.operator(loc, name, params, block, prec),
             Stmt::Return(expr) => self.retur(loc, expr),
-            Stmt::Break => self.brek(loc),
-            Stmt::Continue => self.cont(loc),
+            Stmt::Break(label, expr) => self.brek(loc, label, expr),
+            Stmt::Continue(label) => self.cont(loc, label),
             Stmt::Struct(name, fields) => self.struc(loc, name, fields),
             Stmt::AssignStruct(expr1, name, expr2) => self.assignstruc(loc, expr1, name, expr2),
-            Stmt::Impl(name, block) => self.imp(loc, name, block),
+            Stmt::Impl(name, trait_name, block) => self.imp(loc, name, trait_name, block),
+            Stmt::Throw(expr) => self.throw(loc, expr),
+            Stmt::Trait(name, required, defaults) => self.trai(loc, name, required, defaults),
+            Stmt::Const(ident, expr) => self.cons(loc, ident, expr),
+            Stmt::Yield(expr) => self.yiel(loc, expr),
+            Stmt::For(ident, expr, block, label) => self.fors(loc, ident, expr, block, label),
+            Stmt::Assert(cond, msg) => self.asser(loc, cond, msg),
+            Stmt::Test(name, block) => self.test(loc, name, block),
         }
     }
 
@@ -28,8 +36,9 @@ pub trait StmtVisitor<T> {
     fn assignindex(&mut self, loc: Location, ls: LExpr, idx: LExpr, val: LExpr) -> Result<T, Error>;
     fn block(&mut self, loc: Location, block: Vec<LStmt>) -> Result<T, Error>;
     fn if_else(&mut self, loc: Location, blocks: Vec<(LExpr, Vec<LStmt>)>, els: Option<Block>) -> Result<T, Error>;
-    fn whiles(&mut self, loc: Location, cond: LExpr, block: Vec<LStmt>) -> Result<T, Error>;
+    fn whiles(&mut self, loc: Location, cond: LExpr, block: Vec<LStmt>, label: Option<Identifier>) -> Result<T, Error>;
     fn fun(&mut self, loc: Location, name: Identifier, params: Vec<Identifier>, block: Vec<LStmt>) -> Result<T, Error>;
+    fn gen_fun(&mut self, loc: Location, name: Identifier, params: Vec<Identifier>, block: Vec<LStmt>) -> Result<T, Error>;
     fn operator(
         &mut self,
         loc: Location,
@@ -38,12 +47,32 @@ pub trait StmtVisitor<T> {
         block: Vec<LStmt>,
         prec: Precedence,
     ) -> Result<T, Error>;
-    fn cont(&mut self, loc: Location) -> Result<T, Error>;
-    fn brek(&mut self, loc: Location) -> Result<T, Error>;
+    fn cont(&mut self, loc: Location, label: Option<Identifier>) -> Result<T, Error>;
+    fn brek(&mut self, loc: Location, label: Option<Identifier>, expr: Option<LExpr>) -> Result<T, Error>;
     fn retur(&mut self, loc: Location, expr: LExpr) -> Result<T, Error>;
-    fn struc(&mut self, loc: Location, name: Identifier, fields: Vec<Identifier>) -> Result<T, Error>;
+    fn struc(&mut self, loc: Location, name: Identifier, fields: Vec<(Identifier, Option<LExpr>)>) -> Result<T, Error>;
     fn assignstruc(&mut self, loc: Location, expr1: LExpr, name: Identifier, expr2: LExpr) -> Result<T, Error>;
-    fn imp(&mut self, loc: Location, name: Identifier, block: Vec<LStmt>) -> Result<T, Error>;
+    fn imp(&mut self, loc: Location, name: Identifier, trait_name: Option<Identifier>, block: Vec<LStmt>) -> Result<T, Error>;
+    fn throw(&mut self, loc: Location, expr: LExpr) -> Result<T, Error>;
+    fn trai(
+        &mut self,
+        loc: Location,
+        name: Identifier,
+        required: Vec<(Identifier, Vec<Identifier>)>,
+        defaults: Vec<LStmt>,
+    ) -> Result<T, Error>;
+    fn cons(&mut self, loc: Location, ident: Identifier, expr: LExpr) -> Result<T, Error>;
+    fn yiel(&mut self, loc: Location, expr: LExpr) -> Result<T, Error>;
+    fn fors(
+        &mut self,
+        loc: Location,
+        ident: Identifier,
+        expr: LExpr,
+        block: Vec<LStmt>,
+        label: Option<Identifier>,
+    ) -> Result<T, Error>;
+    fn asser(&mut self, loc: Location, cond: LExpr, msg: Option<LExpr>) -> Result<T, Error>;
+    fn test(&mut self, loc: Location, name: Identifier, block: Vec<LStmt>) -> Result<T, Error>;
 }
 
 pub trait ExprVisitor<T> {
@@ -52,6 +81,7 @@ pub trait ExprVisitor<T> {
         match expr.val {
             Expr::Unit => self.unit(loc),
             Expr::Int(n) => self.int(loc, n),
+            Expr::BigInt(n) => self.bigint(loc, n),
             Expr::Float(n) => self.float(loc, n),
             Expr::String(s) => self.string(loc, s),
             Expr::Bool(b) => self.bool(loc, b),
@@ -65,11 +95,16 @@ pub trait ExprVisitor<T> {
             Expr::Lambda(params, body) => self.lambda(loc, params, body),
             Expr::FieldAccess(expr, name) => self.field(loc, *expr, name),
             Expr::MethodAccess(expr, name, args) => self.method(loc, *expr, name, args),
+            Expr::SafeFieldAccess(expr, name) => self.safe_field(loc, *expr, name),
+            Expr::SafeMethodAccess(expr, name, args) => self.safe_method(loc, *expr, name, args),
+            Expr::StructLiteral(name, fields, base) => self.struct_literal(loc, name, fields, base.map(|b| *b)),
+            Expr::Loop(block, label) => self.loops(loc, block, label),
         }
     }
     fn unit(&mut self, loc: Location) -> Result<T, Error>;
-    fn int(&mut self, loc: Location, n: i32) -> Result<T, Error>;
-    fn float(&mut self, loc: Location, n: f32) -> Result<T, Error>;
+    fn int(&mut self, loc: Location, n: i64) -> Result<T, Error>;
+    fn bigint(&mut self, loc: Location, n: BigInt) -> Result<T, Error>;
+    fn float(&mut self, loc: Location, n: f64) -> Result<T, Error>;
     fn string(&mut self, loc: Location, s: String) -> Result<T, Error>;
     fn bool(&mut self, loc: Location, b: bool) -> Result<T, Error>;
     fn identifier(&mut self, loc: Location, ident: String) -> Result<T, Error>;
@@ -82,4 +117,14 @@ pub trait ExprVisitor<T> {
     fn lambda(&mut self, loc: Location, params: Vec<Identifier>, body: Vec<LStmt>) -> Result<T, Error>;
     fn field(&mut self, loc: Location, expr: LExpr, name: Identifier) -> Result<T, Error>;
     fn method(&mut self, loc: Location, callee: LExpr, name: Identifier, args: Vec<LExpr>) -> Result<T, Error>;
+    fn safe_field(&mut self, loc: Location, expr: LExpr, name: Identifier) -> Result<T, Error>;
+    fn safe_method(&mut self, loc: Location, callee: LExpr, name: Identifier, args: Vec<LExpr>) -> Result<T, Error>;
+    fn struct_literal(
+        &mut self,
+        loc: Location,
+        name: Identifier,
+        fields: Vec<(Identifier, LExpr)>,
+        base: Option<LExpr>,
+    ) -> Result<T, Error>;
+    fn loops(&mut self, loc: Location, block: Vec<LStmt>, label: Option<Identifier>) -> Result<T, Error>;
 }